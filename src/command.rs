@@ -0,0 +1,41 @@
+use std::collections::BTreeMap;
+
+/// A pipe message driving the plugin without its ui, its arguments are taken from the payload
+/// (`switch -- work`) or from the args (`switch --args session=work`)
+pub enum Command {
+    NextSession,
+    PrevSession,
+    LastSession,
+    Switch(String),
+    FocusTab(String, usize),
+    Kill(String),
+    ExpandAll,
+}
+
+impl Command {
+    /// None if the message is not a command, an error if its arguments are invalid
+    pub fn from_pipe(name: &str, payload: Option<&str>, args: &BTreeMap<String, String>) -> Option<Result<Self, String>> {
+        let mut words = payload.unwrap_or_default().split_whitespace();
+        let mut argument = |arg: &str| {
+            words.next()
+                .map(|word| word.to_string())
+                .or(args.get(arg).cloned())
+                .ok_or(format!("{} requires a {}", name, arg))
+        };
+        let command = match name {
+            "next-session" => Ok(Command::NextSession),
+            "prev-session" => Ok(Command::PrevSession),
+            "last-session" => Ok(Command::LastSession),
+            "switch" => argument("session").map(Command::Switch),
+            "focus-tab" => argument("session").and_then(|session| {
+                let position = argument("position")?;
+                let position = position.parse().map_err(|_| format!("invalid tab position '{}'", position))?;
+                Ok(Command::FocusTab(session, position))
+            }),
+            "kill" => argument("session").map(Command::Kill),
+            "expand-all" => Ok(Command::ExpandAll),
+            _ => return None,
+        };
+        Some(command)
+    }
+}
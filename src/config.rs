@@ -1,15 +1,224 @@
 use std::collections::BTreeMap;
+use regex::Regex;
+use zellij_tile::prelude::{BareKey, KeyWithModifier};
+
+use crate::keybinds::Keybinds;
+use crate::sort::SortOrder;
 
 #[derive(Default)]
 pub struct Config {
     pub show_plugins: bool,
+    pub confirm_kill: bool,
+    pub undo_delay: f64,
+    pub sort: SortOrder,
+    pub group_by: Option<GroupBy>,
+    pub keybinds: Keybinds,
+    pub hint_alphabet: HintAlphabet,
+    // invalid options, reported once the plugin is loaded
+    pub errors: Vec<String>,
 }
 
 impl From<BTreeMap<String, String>> for Config {
     fn from(config: BTreeMap<String, String>) -> Self {
+        let mut errors = Vec::new();
+        let keybinds = match config.get("keybinds").map(|keybinds| Keybinds::parse(keybinds)) {
+            Some(Ok(keybinds)) => keybinds,
+            Some(Err(error)) => {
+                errors.push(format!("keybinds: {}", error));
+                Keybinds::default()
+            }
+            None => Keybinds::default(),
+        };
+        let sort = match config.get("sort").map(|sort| sort.parse()) {
+            Some(Ok(sort)) => sort,
+            Some(Err(error)) => {
+                errors.push(format!("sort: {}", error));
+                SortOrder::default()
+            }
+            None => SortOrder::default(),
+        };
+        let group_by = match config.get("group_by").map(|group_by| GroupBy::parse(group_by)) {
+            Some(Ok(group_by)) => Some(group_by),
+            Some(Err(error)) => {
+                errors.push(format!("group_by: {}", error));
+                None
+            }
+            None => None,
+        };
+        let hint_alphabet = match config.get("hint_alphabet").map(|alphabet| HintAlphabet::parse(alphabet)) {
+            Some(Ok(alphabet)) => alphabet,
+            Some(Err(error)) => {
+                errors.push(format!("hint_alphabet: {}", error));
+                HintAlphabet::default()
+            }
+            None => HintAlphabet::default(),
+        };
+        let (hint_alphabet, unbound) = hint_alphabet.without_keybinds(&keybinds);
+        errors.extend(unbound.into_iter().map(|error| format!("hint_alphabet: {}", error)));
         Self {
             show_plugins: config.get("show_plugins").map(|s| s == "true").unwrap_or(false),
+            confirm_kill: config.get("confirm_kill").map(|s| s == "true").unwrap_or(true),
+            undo_delay: config.get("undo_delay").and_then(|s| s.parse().ok()).unwrap_or(0.0),
+            sort,
+            group_by,
+            keybinds,
+            hint_alphabet,
+            errors,
+        }
+    }
+}
+
+/// How sessions are grouped by name, from the `group_by` option
+pub enum GroupBy {
+    // the name up to the separator, `client/project` being in the `client` group
+    Separator(String),
+    // the first capture group, or the whole match if there is none
+    Regex(Regex),
+}
+
+impl GroupBy {
+    pub fn parse(group_by: &str) -> Result<Self, String> {
+        match group_by.split_once(':') {
+            Some(("separator", "")) => Err("empty separator".to_string()),
+            Some(("separator", separator)) => Ok(GroupBy::Separator(separator.to_string())),
+            Some(("regex", regex)) => Regex::new(regex)
+                .map(GroupBy::Regex)
+                .map_err(|error| error.to_string()),
+            _ => Err(format!("expected separator:<separator> or regex:<regex>, got '{}'", group_by)),
+        }
+    }
+
+    /// The group of a session, None if it is not part of one
+    pub fn group(&self, name: &str) -> Option<String> {
+        match self {
+            GroupBy::Separator(separator) => name.split_once(separator.as_str()).map(|(group, _)| group.to_string()),
+            GroupBy::Regex(regex) => {
+                let captures = regex.captures(name)?;
+                captures.get(1).or(captures.get(0)).map(|group| group.as_str().to_string())
+            }
+        }
+    }
+}
+
+/// The characters quick-select hints are made of, from the `hint_alphabet` option
+#[derive(Clone)]
+pub struct HintAlphabet(Vec<char>);
+
+impl Default for HintAlphabet {
+    fn default() -> Self {
+        Self(('0'..='9').chain('A'..='Z').collect())
+    }
+}
+
+impl HintAlphabet {
+    pub fn parse(alphabet: &str) -> Result<Self, String> {
+        let mut chars: Vec<char> = Vec::new();
+        for c in alphabet.chars() {
+            if c.is_whitespace() {
+                return Err("whitespace cannot be part of a hint".to_string());
+            }
+            if chars.contains(&c) {
+                return Err(format!("'{}' appears twice", c));
+            }
+            chars.push(c);
+        }
+        match chars.len() {
+            0 | 1 => Err("expected at least two characters".to_string()),
+            _ => Ok(Self(chars)),
+        }
+    }
+
+    /// Leave out the characters bound to an action, so that keybinds win over hints, along with
+    /// a description of each of them
+    pub fn without_keybinds(self, keybinds: &Keybinds) -> (Self, Vec<String>) {
+        let mut errors = Vec::new();
+        let chars: Vec<char> = self.0.into_iter()
+            .filter(|&c| match keybinds.action(&KeyWithModifier::new(BareKey::Char(c))) {
+                Some(action) => {
+                    errors.push(format!("'{}' is bound to {}", c, action));
+                    false
+                }
+                None => true,
+            })
+            .collect();
+        match chars.len() {
+            0 | 1 => {
+                errors.push("not enough unbound characters left, using the default".to_string());
+                (Self::default(), errors)
+            }
+            _ => (Self(chars), errors),
+        }
+    }
+
+    pub fn contains(&self, c: char) -> bool {
+        self.0.contains(&c)
+    }
+
+    /// Hints for a number of rows, none being the prefix of another: single characters while
+    /// there are enough of them, otherwise the last ones start longer hints, as in vimium
+    pub fn hints(&self, count: usize) -> Vec<String> {
+        let mut hints = vec![String::new()];
+        let mut offset = 0;
+        while hints.len() - offset < count || offset == 0 {
+            let prefix = hints[offset].clone();
+            offset += 1;
+            hints.extend(self.0.iter().map(|c| format!("{}{}", prefix, c)));
         }
+        hints.drain(offset..offset + count).collect()
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(options: &[(&str, &str)]) -> Config {
+        Config::from(options.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect::<BTreeMap<_, _>>())
+    }
+
+    fn group(group_by: &str, name: &str) -> Option<String> {
+        GroupBy::parse(group_by).unwrap().group(name)
+    }
+
+    #[test]
+    fn group_by_separator_groups_by_the_first_part() {
+        assert_eq!(group("separator:/", "acme/api"), Some("acme".to_string()));
+        assert_eq!(group("separator:/", "acme/api/v2"), Some("acme".to_string()));
+        assert_eq!(group("separator:/", "dotfiles"), None);
+        assert_eq!(group("separator:--", "acme--api"), Some("acme".to_string()));
+    }
+
+    #[test]
+    fn group_by_regex_uses_the_first_capture_group_or_the_match() {
+        assert_eq!(group("regex:^([a-z]+)-", "area-project"), Some("area".to_string()));
+        assert_eq!(group("regex:^[a-z]+-", "area-project"), Some("area-".to_string()));
+        assert_eq!(group("regex:^([a-z]+)-", "Project"), None);
+        // a session can be in a group of its own name
+        assert_eq!(group("regex:^(.*)$", "work"), Some("work".to_string()));
+    }
+
+    #[test]
+    fn group_by_rejects_invalid_options() {
+        assert_eq!(GroupBy::parse("separator:").err(), Some("empty separator".to_string()));
+        assert!(GroupBy::parse("regex:(").is_err());
+        assert!(GroupBy::parse("prefix:/").is_err());
+        assert!(GroupBy::parse("separator").is_err());
+        assert_eq!(parse(&[("group_by", "separator:")]).errors, ["group_by: empty separator"]);
+    }
+
+    #[test]
+    fn hint_alphabet_leaves_out_bound_keys() {
+        let config = parse(&[("hint_alphabet", "asdfghjkl")]);
+        assert_eq!(config.hint_alphabet.hints(4), ["a", "d", "f", "g"]);
+        assert_eq!(config.errors, [
+            "hint_alphabet: 's' is bound to sort",
+            "hint_alphabet: 'h' is bound to collapse",
+            "hint_alphabet: 'j' is bound to down",
+            "hint_alphabet: 'k' is bound to up",
+            "hint_alphabet: 'l' is bound to expand",
+        ]);
+        let config = parse(&[("hint_alphabet", "asdfghjkl"), ("keybinds", "sort \"S\"")]);
+        assert!(config.hint_alphabet.contains('s'));
+        assert_eq!(config.errors.len(), 4);
+    }
+}
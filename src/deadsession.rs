@@ -0,0 +1,73 @@
+use zellij_tile::prelude::*;
+use serde_json::{json, Value};
+use std::time::Duration;
+
+use crate::host::{Host, HostCommand};
+use crate::preview::PaneGeometry;
+use crate::sessiontree::{Node, NodeKey};
+use crate::utils::{format_age, highlight_mark, highlight_matches};
+
+pub struct DeadSession {
+    name: String,
+    age: Duration,
+}
+
+impl DeadSession {
+    pub fn new(name: String, age: Duration) -> Self {
+        Self {
+            name,
+            age,
+        }
+    }
+}
+
+impl Node for DeadSession {
+    fn identifier(&self) -> String {
+        self.name.clone()
+    }
+    fn key(&self) -> NodeKey {
+        NodeKey::DeadSession(self.name.clone())
+    }
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn is_focused(&self) -> bool {
+        false
+    }
+    fn focus(&self, host: &dyn Host) -> Result<(), String> {
+        // switching to an exited session resurrects it
+        host.run(HostCommand::SwitchSession(self.name.clone()));
+        host.run(HostCommand::HideSelf);
+        Ok(())
+    }
+    fn kill(&self, host: &dyn Host) -> Result<(), String> {
+        host.run(HostCommand::DeleteDeadSession(self.name.clone()));
+        Ok(())
+    }
+    fn can_rename(&self) -> Result<(), String> {
+        Err("cannot rename an exited session".to_string())
+    }
+    fn rename(&self, _host: &dyn Host, _name: &str) -> Result<(), String> {
+        self.can_rename()
+    }
+    fn geometry(&self) -> Option<PaneGeometry> {
+        None
+    }
+    fn to_json(&self) -> Value {
+        json!({
+            "session": self.name,
+            "is_current": false,
+            "exited": true,
+        })
+    }
+    fn render(&self, prefix: &str, is_selected: bool, is_marked: bool, matches: &[usize]) -> NestedListItem {
+        let text = format!("{0}{1} (exited {2})", prefix, self.name, format_age(self.age));
+        let text_len = text.chars().count();
+        let item = match is_selected {
+            true => NestedListItem::new(text).color_range(0, 0..text_len).selected(),
+            false => NestedListItem::new(text),
+        };
+        let item = highlight_mark(item, prefix, is_marked);
+        highlight_matches(item, prefix.chars().count(), matches)
+    }
+}
@@ -0,0 +1,56 @@
+use zellij_tile::prelude::*;
+use std::collections::BTreeMap;
+
+use crate::host::{Host, HostCommand};
+use crate::picker::PickFormat;
+use crate::sessiontree::SessionTree;
+
+/// A `dump` request, answered with the whole tree without showing the plugin
+///
+/// Plugins receive a `dump_result` message with the request's args, the payload being the tree.
+/// Requests received before the first session update are answered once it arrives.
+pub struct DumpRequest {
+    format: PickFormat,
+    source: PipeSource,
+    args: BTreeMap<String, String>,
+}
+
+impl DumpRequest {
+    pub fn is_dump(name: &str) -> bool {
+        name == "dump"
+    }
+
+    pub fn new(source: PipeSource, args: BTreeMap<String, String>) -> Self {
+        let format = PickFormat::from_args(&args);
+        Self { format, source, args }
+    }
+
+    /// Keep a cli pipe open until the tree is available
+    pub fn defer(&self, host: &dyn Host) {
+        if let PipeSource::Cli(pipe_id) = &self.source {
+            host.run(HostCommand::BlockCliPipeInput(pipe_id.clone()));
+        }
+    }
+
+    pub fn reply(self, host: &dyn Host, session_tree: &SessionTree) {
+        let payload = match self.format {
+            PickFormat::Text => session_tree.dump_text(),
+            PickFormat::Json => session_tree.dump_json().to_string(),
+        };
+        match self.source {
+            PipeSource::Plugin(plugin_id) => {
+                host.run(HostCommand::MessageToPlugin {
+                    name: "dump_result".to_string(),
+                    plugin_id,
+                    args: self.args,
+                    payload: Some(payload),
+                });
+            }
+            PipeSource::Cli(pipe_id) => {
+                host.run(HostCommand::CliPipeOutput(pipe_id.clone(), payload));
+                host.run(HostCommand::UnblockCliPipeInput(pipe_id));
+            }
+            PipeSource::Keybind => (),
+        }
+    }
+}
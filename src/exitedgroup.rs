@@ -0,0 +1,63 @@
+use zellij_tile::prelude::*;
+use serde_json::{json, Value};
+
+use crate::host::{Host, HostCommand};
+use crate::preview::PaneGeometry;
+use crate::sessiontree::{Node, NodeKey};
+use crate::utils::{highlight_mark, highlight_matches};
+
+pub struct ExitedGroup {
+    sessions: usize,
+}
+
+impl ExitedGroup {
+    pub fn new(sessions: usize) -> Self {
+        Self {
+            sessions,
+        }
+    }
+}
+
+impl Node for ExitedGroup {
+    fn identifier(&self) -> String {
+        "exited".to_string()
+    }
+    fn key(&self) -> NodeKey {
+        NodeKey::Exited
+    }
+    fn name(&self) -> String {
+        "exited".to_string()
+    }
+    fn is_focused(&self) -> bool {
+        false
+    }
+    fn focus(&self, _host: &dyn Host) -> Result<(), String> {
+        Err("cannot switch to a group".to_string())
+    }
+    fn kill(&self, host: &dyn Host) -> Result<(), String> {
+        host.run(HostCommand::DeleteAllDeadSessions);
+        Ok(())
+    }
+    fn can_rename(&self) -> Result<(), String> {
+        Err("cannot rename a group".to_string())
+    }
+    fn rename(&self, _host: &dyn Host, _name: &str) -> Result<(), String> {
+        self.can_rename()
+    }
+    fn geometry(&self) -> Option<PaneGeometry> {
+        None
+    }
+    fn to_json(&self) -> Value {
+        json!({})
+    }
+    fn render(&self, prefix: &str, is_selected: bool, is_marked: bool, matches: &[usize]) -> NestedListItem {
+        let text = format!("{0}exited ({1})", prefix, self.sessions);
+        let text_len = text.chars().count();
+        let item = match is_selected {
+            true => NestedListItem::new(text).color_range(0, 0..text_len).selected(),
+            false => NestedListItem::new(text),
+        };
+        let item = highlight_mark(item, prefix, is_marked);
+        highlight_matches(item, prefix.chars().count(), matches)
+    }
+}
@@ -0,0 +1,192 @@
+use zellij_tile::prelude::*;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// A session update stored under `fixtures/`, along with the plugin configuration to load
+///
+/// Sessions, tabs and panes have the fields of zellij's `SessionInfo`, `TabInfo` and `PaneInfo`,
+/// panes being listed by tab position as in `PaneManifest`. Only the fields the plugin reads are
+/// loaded, the others are ignored so that captures such as `sample.json` can be kept whole.
+#[derive(Deserialize)]
+pub struct Fixture {
+    #[serde(default)]
+    pub config: BTreeMap<String, String>,
+    pub sessions: Vec<SessionFixture>,
+    #[serde(default)]
+    pub resurrectable: Vec<DeadSessionFixture>,
+}
+
+#[derive(Deserialize)]
+pub struct SessionFixture {
+    name: String,
+    #[serde(default)]
+    is_current_session: bool,
+    #[serde(default)]
+    connected_clients: usize,
+    #[serde(default)]
+    tabs: Vec<TabFixture>,
+    #[serde(default)]
+    panes: HashMap<usize, Vec<PaneFixture>>,
+}
+
+#[derive(Deserialize)]
+pub struct TabFixture {
+    position: usize,
+    name: String,
+    #[serde(default)]
+    active: bool,
+}
+
+#[derive(Deserialize)]
+pub struct PaneFixture {
+    id: u32,
+    title: String,
+    #[serde(default)]
+    is_plugin: bool,
+    #[serde(default)]
+    is_focused: bool,
+    #[serde(default)]
+    is_floating: bool,
+    terminal_command: Option<String>,
+    plugin_url: Option<String>,
+    pane_x: usize,
+    pane_y: usize,
+    pane_rows: usize,
+    pane_columns: usize,
+}
+
+#[derive(Deserialize)]
+pub struct DeadSessionFixture {
+    name: String,
+    age_secs: u64,
+}
+
+impl Fixture {
+    pub fn load(name: &str) -> Self {
+        let path = fixtures_dir().join(format!("{}.json", name));
+        let fixture = fs::read_to_string(&path).unwrap_or_else(|error| panic!("cannot read {}: {}", path.display(), error));
+        serde_json::from_str(&fixture).unwrap_or_else(|error| panic!("invalid fixture {}: {}", path.display(), error))
+    }
+
+    pub fn session_update(&self) -> Event {
+        let sessions = self.sessions.iter().map(SessionInfo::from).collect();
+        let resurrectable = self.resurrectable.iter()
+            .map(|session| (session.name.clone(), Duration::from_secs(session.age_secs)))
+            .collect();
+        Event::SessionUpdate(sessions, resurrectable)
+    }
+}
+
+impl From<&SessionFixture> for SessionInfo {
+    fn from(session: &SessionFixture) -> Self {
+        let tabs = session.tabs.iter().map(|tab| TabInfo {
+            position: tab.position,
+            name: tab.name.clone(),
+            active: tab.active,
+            ..Default::default()
+        }).collect();
+        let panes = session.panes.iter()
+            .map(|(&position, panes)| (position, panes.iter().map(PaneInfo::from).collect()))
+            .collect();
+        SessionInfo {
+            name: session.name.clone(),
+            tabs,
+            panes: PaneManifest { panes },
+            connected_clients: session.connected_clients,
+            is_current_session: session.is_current_session,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&PaneFixture> for PaneInfo {
+    fn from(pane: &PaneFixture) -> Self {
+        PaneInfo {
+            id: pane.id,
+            is_plugin: pane.is_plugin,
+            is_focused: pane.is_focused,
+            is_floating: pane.is_floating,
+            title: pane.title.clone(),
+            pane_x: pane.pane_x,
+            pane_y: pane.pane_y,
+            pane_rows: pane.pane_rows,
+            pane_columns: pane.pane_columns,
+            terminal_command: pane.terminal_command.clone(),
+            plugin_url: pane.plugin_url.clone(),
+            is_selectable: true,
+            ..Default::default()
+        }
+    }
+}
+
+fn fixtures_dir() -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("fixtures")
+}
+
+/// A readable line for a rendered item: `>` when selected, two spaces per indentation level,
+/// then the text followed by the ranges of each color, eg. `  ▸ (1) foo  [1: 4-6]`
+pub fn describe_item(item: &NestedListItem) -> String {
+    let serialized = item.serialize();
+    let content = serialized.trim_start_matches('|');
+    let indentation = serialized.len() - content.len();
+    let (is_selected, content) = match content.strip_prefix('x') {
+        Some(content) => (true, content),
+        None => (false, content),
+    };
+    let content = content.strip_prefix('z').unwrap_or(content);
+    // color levels are each terminated by a `$`, the text comes last as comma separated bytes
+    let mut parts: Vec<&str> = content.split('$').collect();
+    let bytes = parts.pop().unwrap_or_default();
+    let bytes: Vec<u8> = bytes.split(',').filter(|byte| !byte.is_empty()).map(|byte| byte.parse().unwrap()).collect();
+    let text = String::from_utf8(bytes).unwrap();
+    let colors: Vec<String> = parts.iter()
+        .enumerate()
+        .filter(|(_, indices)| !indices.is_empty())
+        .map(|(level, indices)| {
+            let indices: Vec<usize> = indices.split(',').map(|index| index.parse().unwrap()).collect();
+            format!("{}: {}", level, describe_ranges(&indices))
+        })
+        .collect();
+    let marker = match is_selected {
+        true => ">",
+        false => " ",
+    };
+    let mut line = format!("{}{}{}", marker, "  ".repeat(indentation), text);
+    if !colors.is_empty() {
+        line = format!("{}  [{}]", line, colors.join(", "));
+    }
+    line
+}
+
+/// "0-3,7" for the indices 0, 1, 2, 3 and 7
+fn describe_ranges(indices: &[usize]) -> String {
+    let mut ranges: Vec<(usize, usize)> = Vec::new();
+    for &index in indices {
+        match ranges.last_mut() {
+            Some((_, end)) if *end + 1 == index => *end = index,
+            _ => ranges.push((index, index)),
+        }
+    }
+    ranges.iter()
+        .map(|(start, end)| match start == end {
+            true => start.to_string(),
+            false => format!("{}-{}", start, end),
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+/// Compare with `fixtures/snapshots/<name>.snap`, written instead when `UPDATE_SNAPSHOTS` is set
+pub fn assert_snapshot(name: &str, actual: &str) {
+    let path = fixtures_dir().join("snapshots").join(format!("{}.snap", name));
+    if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = fs::read_to_string(&path)
+        .unwrap_or_else(|_| panic!("missing snapshot {}, run the tests with UPDATE_SNAPSHOTS=1", path.display()));
+    assert_eq!(expected, actual, "snapshot {} differs, run the tests with UPDATE_SNAPSHOTS=1 to accept the changes", name);
+}
@@ -0,0 +1,83 @@
+use zellij_tile::prelude::*;
+use serde_json::{json, Value};
+
+use crate::host::{Host, HostCommand};
+use crate::preview::PaneGeometry;
+use crate::sessiontree::{Node, NodeKey};
+use crate::utils::{highlight_mark, highlight_matches, pluralize};
+
+/// Sessions sharing a name prefix, see the `group_by` option
+pub struct Group {
+    name: String,
+    sessions: Vec<String>,
+    is_focused: bool,
+    tabs: usize,
+    panes: usize,
+}
+
+impl Group {
+    pub fn new(name: String) -> Self {
+        Self {
+            name,
+            sessions: Vec::new(),
+            is_focused: false,
+            tabs: 0,
+            panes: 0,
+        }
+    }
+
+    /// Add a session to the group along with its number of tabs and panes
+    pub fn add_session(&mut self, name: String, is_current_session: bool, tabs: usize, panes: usize) {
+        self.sessions.push(name);
+        self.is_focused |= is_current_session;
+        self.tabs += tabs;
+        self.panes += panes;
+    }
+}
+
+impl Node for Group {
+    fn identifier(&self) -> String {
+        self.name.clone()
+    }
+    fn key(&self) -> NodeKey {
+        NodeKey::Group(self.name.clone())
+    }
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+    fn is_focused(&self) -> bool {
+        self.is_focused
+    }
+    fn focus(&self, _host: &dyn Host) -> Result<(), String> {
+        Err("cannot switch to a group".to_string())
+    }
+    fn kill(&self, host: &dyn Host) -> Result<(), String> {
+        host.run(HostCommand::KillSessions(self.sessions.clone()));
+        Ok(())
+    }
+    fn can_rename(&self) -> Result<(), String> {
+        Err("cannot rename a group".to_string())
+    }
+    fn rename(&self, _host: &dyn Host, _name: &str) -> Result<(), String> {
+        self.can_rename()
+    }
+    fn geometry(&self) -> Option<PaneGeometry> {
+        None
+    }
+    fn to_json(&self) -> Value {
+        json!({
+            "group": self.name,
+        })
+    }
+    fn render(&self, prefix: &str, is_selected: bool, is_marked: bool, matches: &[usize]) -> NestedListItem {
+        let text = format!("{0}{1} ({2}, {3}, {4})", prefix, self.name,
+            pluralize(self.sessions.len(), "session"), pluralize(self.tabs, "tab"), pluralize(self.panes, "pane"));
+        let text_len = text.chars().count();
+        let item = match is_selected {
+            true => NestedListItem::new(text).color_range(0, 0..text_len).selected(),
+            false => NestedListItem::new(text),
+        };
+        let item = highlight_mark(item, prefix, is_marked);
+        highlight_matches(item, prefix.chars().count(), matches)
+    }
+}
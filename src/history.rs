@@ -0,0 +1,76 @@
+use crate::host::Host;
+
+// in the data directory, shared by the plugin instances of every session
+const HISTORY_FILE: &str = "history";
+const MAX_HISTORY: usize = 100;
+
+/// Sessions switched to through the plugin, most recent first
+#[derive(Default)]
+pub struct History {
+    sessions: Vec<String>,
+}
+
+impl History {
+    pub fn load(host: &dyn Host) -> Self {
+        let sessions = host.read_data(HISTORY_FILE)
+            .map(|history| history.lines().map(|line| line.to_string()).collect())
+            .unwrap_or_default();
+        Self { sessions }
+    }
+
+    /// Record a switch from one session to another, reloading first as another session may have
+    /// written to the history since
+    pub fn record(&mut self, host: &dyn Host, from: &str, to: &str) -> Result<(), String> {
+        *self = Self::load(host);
+        for name in [from, to] {
+            self.sessions.retain(|session| session != name);
+            self.sessions.insert(0, name.to_string());
+        }
+        self.sessions.truncate(MAX_HISTORY);
+        host.write_data(HISTORY_FILE, &self.sessions.join("\n"))
+            .map_err(|error| format!("cannot save session history: {}", error))
+    }
+
+    /// The most recent session other than the current one
+    pub fn last(&self, current: &str) -> Option<&str> {
+        self.sessions.iter()
+            .map(|session| session.as_str())
+            .find(|session| *session != current)
+    }
+
+    /// Position of the session in the history, None if it was never switched to
+    pub fn rank(&self, name: &str) -> Option<usize> {
+        self.sessions.iter().position(|session| session == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::RecordingHost;
+
+    #[test]
+    fn switches_move_both_sessions_to_the_front() {
+        let host = RecordingHost::default();
+        let mut history = History::default();
+        history.record(&host, "alpha", "beta").unwrap();
+        history.record(&host, "gamma", "alpha").unwrap();
+        assert_eq!(history.sessions, ["alpha", "gamma", "beta"]);
+        assert_eq!(history.last("alpha"), Some("gamma"));
+        // reloaded from the data directory
+        assert_eq!(History::load(&host).rank("beta"), Some(2));
+        assert_eq!(history.rank("delta"), None);
+    }
+
+    #[test]
+    fn only_the_most_recent_sessions_are_kept() {
+        let host = RecordingHost::default();
+        let mut history = History::default();
+        for i in 0..MAX_HISTORY {
+            history.record(&host, &format!("session-{}", i), &format!("session-{}", i + 1)).unwrap();
+        }
+        assert_eq!(history.sessions.len(), MAX_HISTORY);
+        assert_eq!(history.sessions.first().map(|session| session.as_str()), Some("session-100"));
+        assert_eq!(history.rank("session-0"), None);
+    }
+}
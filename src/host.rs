@@ -0,0 +1,153 @@
+use zellij_tile::prelude::*;
+use std::collections::BTreeMap;
+
+/// A command issued to zellij, see the shims of the same name in zellij-tile
+#[derive(Clone, Debug, PartialEq)]
+pub enum HostCommand {
+    RequestPermission(Vec<PermissionType>),
+    Subscribe(Vec<EventType>),
+    SetTimeout(f64),
+    SwitchSession(String),
+    SwitchSessionWithLayout(String, LayoutInfo),
+    SwitchSessionWithFocus(String, Option<usize>, Option<(u32, bool)>),
+    FocusTerminalPane(u32),
+    FocusOrCreateTab(String),
+    KillSessions(Vec<String>),
+    DeleteDeadSession(String),
+    DeleteAllDeadSessions,
+    CloseTab(usize),
+    ClosePane((u32, bool)),
+    RenameSession(String),
+    RenameTab(u32, String),
+    RenamePane((u32, bool), String),
+    HideSelf,
+    CloseSelf,
+    BlockCliPipeInput(String),
+    UnblockCliPipeInput(String),
+    CliPipeOutput(String, String),
+    MessageToPlugin {
+        name: String,
+        plugin_id: u32,
+        args: BTreeMap<String, String>,
+        payload: Option<String>,
+    },
+}
+
+/// Where the commands of the plugin go, zellij itself outside of tests
+pub trait Host {
+    fn run(&self, command: HostCommand);
+    fn print_nested_list(&self, items: Vec<NestedListItem>, x: usize, y: usize, width: usize, height: usize);
+    /// A file of the plugin's data directory, shared by the plugin instances of every session
+    fn read_data(&self, name: &str) -> Option<String>;
+    fn write_data(&self, name: &str, contents: &str) -> Result<(), String>;
+}
+
+impl Default for Box<dyn Host> {
+    fn default() -> Self {
+        Box::new(DefaultHost::default())
+    }
+}
+
+#[cfg(not(test))]
+type DefaultHost = ZellijHost;
+#[cfg(test)]
+type DefaultHost = RecordingHost;
+
+/// Runs the commands through the zellij-tile shims, which only link on wasm
+#[cfg(not(test))]
+#[derive(Default)]
+pub struct ZellijHost;
+
+#[cfg(not(test))]
+impl Host for ZellijHost {
+    fn run(&self, command: HostCommand) {
+        match command {
+            HostCommand::RequestPermission(permissions) => request_permission(&permissions),
+            HostCommand::Subscribe(event_types) => subscribe(&event_types),
+            HostCommand::SetTimeout(secs) => set_timeout(secs),
+            HostCommand::SwitchSession(name) => switch_session(Some(&name)),
+            HostCommand::SwitchSessionWithLayout(name, layout) => switch_session_with_layout(Some(&name), layout, None),
+            HostCommand::SwitchSessionWithFocus(name, tab_position, pane_id) => switch_session_with_focus(&name, tab_position, pane_id),
+            HostCommand::FocusTerminalPane(id) => focus_terminal_pane(id, true),
+            HostCommand::FocusOrCreateTab(name) => focus_or_create_tab(&name),
+            HostCommand::KillSessions(names) => kill_sessions(&names),
+            HostCommand::DeleteDeadSession(name) => delete_dead_session(&name),
+            HostCommand::DeleteAllDeadSessions => delete_all_dead_sessions(),
+            HostCommand::CloseTab(position) => close_tab_with_index(position),
+            HostCommand::ClosePane((id, true)) => close_plugin_pane(id),
+            HostCommand::ClosePane((id, false)) => close_terminal_pane(id),
+            HostCommand::RenameSession(name) => rename_session(&name),
+            HostCommand::RenameTab(position, name) => rename_tab(position, name),
+            HostCommand::RenamePane((id, true), name) => rename_plugin_pane(id, name),
+            HostCommand::RenamePane((id, false), name) => rename_terminal_pane(id, name),
+            HostCommand::HideSelf => hide_self(),
+            HostCommand::CloseSelf => close_self(),
+            HostCommand::BlockCliPipeInput(pipe_id) => block_cli_pipe_input(&pipe_id),
+            HostCommand::UnblockCliPipeInput(pipe_id) => unblock_cli_pipe_input(&pipe_id),
+            HostCommand::CliPipeOutput(pipe_id, output) => cli_pipe_output(&pipe_id, &output),
+            HostCommand::MessageToPlugin { name, plugin_id, args, payload } => {
+                let mut message = MessageToPlugin::new(name)
+                    .with_destination_plugin_id(plugin_id)
+                    .with_args(args);
+                if let Some(payload) = payload {
+                    message = message.with_payload(payload);
+                }
+                pipe_message_to_plugin(message);
+            }
+        }
+    }
+
+    fn print_nested_list(&self, items: Vec<NestedListItem>, x: usize, y: usize, width: usize, height: usize) {
+        print_nested_list_with_coordinates(items, x, y, Some(width), Some(height));
+    }
+
+    fn read_data(&self, name: &str) -> Option<String> {
+        std::fs::read_to_string(format!("/data/{}", name)).ok()
+    }
+
+    fn write_data(&self, name: &str, contents: &str) -> Result<(), String> {
+        std::fs::write(format!("/data/{}", name), contents).map_err(|error| error.to_string())
+    }
+}
+
+/// Keeps the commands and the last rendered list for tests to assert on, along with the data
+/// files in memory, clones share them
+#[cfg(test)]
+#[derive(Clone, Default)]
+pub struct RecordingHost {
+    commands: std::rc::Rc<std::cell::RefCell<Vec<HostCommand>>>,
+    frame: std::rc::Rc<std::cell::RefCell<Vec<NestedListItem>>>,
+    data: std::rc::Rc<std::cell::RefCell<BTreeMap<String, String>>>,
+}
+
+#[cfg(test)]
+impl RecordingHost {
+    /// The commands run since the last call
+    pub fn take(&self) -> Vec<HostCommand> {
+        self.commands.take()
+    }
+
+    pub fn frame(&self) -> Vec<NestedListItem> {
+        self.frame.borrow().clone()
+    }
+}
+
+#[cfg(test)]
+impl Host for RecordingHost {
+    fn run(&self, command: HostCommand) {
+        self.commands.borrow_mut().push(command);
+    }
+
+    fn print_nested_list(&self, items: Vec<NestedListItem>, _x: usize, _y: usize, _width: usize, _height: usize) {
+        *self.frame.borrow_mut() = items;
+    }
+
+    fn read_data(&self, name: &str) -> Option<String> {
+        self.data.borrow().get(name).cloned()
+    }
+
+    fn write_data(&self, name: &str, contents: &str) -> Result<(), String> {
+        self.data.borrow_mut().insert(name.to_string(), contents.to_string());
+        Ok(())
+    }
+}
@@ -0,0 +1,237 @@
+use zellij_tile::prelude::*;
+use std::fmt;
+use std::str::FromStr;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Up,
+    Down,
+    Collapse,
+    Expand,
+    Select,
+    Kill,
+    Mark,
+    Undo,
+    Search,
+    Messages,
+    Rename,
+    NewSession,
+    LastSession,
+    Sort,
+    Quit,
+}
+
+impl FromStr for Action {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "up" => Ok(Action::Up),
+            "down" => Ok(Action::Down),
+            "collapse" => Ok(Action::Collapse),
+            "expand" => Ok(Action::Expand),
+            "select" => Ok(Action::Select),
+            "kill" => Ok(Action::Kill),
+            "mark" => Ok(Action::Mark),
+            "undo" => Ok(Action::Undo),
+            "search" => Ok(Action::Search),
+            "messages" => Ok(Action::Messages),
+            "rename" => Ok(Action::Rename),
+            "new_session" => Ok(Action::NewSession),
+            "last_session" => Ok(Action::LastSession),
+            "sort" => Ok(Action::Sort),
+            "quit" => Ok(Action::Quit),
+            _ => Err(format!("unknown action '{}'", name)),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Action::Up => "up",
+            Action::Down => "down",
+            Action::Collapse => "collapse",
+            Action::Expand => "expand",
+            Action::Select => "select",
+            Action::Kill => "kill",
+            Action::Mark => "mark",
+            Action::Undo => "undo",
+            Action::Search => "search",
+            Action::Messages => "messages",
+            Action::Rename => "rename",
+            Action::NewSession => "new_session",
+            Action::LastSession => "last_session",
+            Action::Sort => "sort",
+            Action::Quit => "quit",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+const DEFAULT_KEYBINDS: &[(Action, &[&str])] = &[
+    (Action::Up, &["k", "Up"]),
+    (Action::Down, &["j", "Down"]),
+    (Action::Collapse, &["h", "Left"]),
+    (Action::Expand, &["l", "Right"]),
+    (Action::Select, &["Enter"]),
+    (Action::Kill, &["x", "Delete"]),
+    (Action::Mark, &["Space"]),
+    (Action::Undo, &["u"]),
+    (Action::Search, &["/"]),
+    (Action::Messages, &["m"]),
+    (Action::Rename, &["r"]),
+    (Action::NewSession, &["n"]),
+    (Action::LastSession, &["Tab"]),
+    (Action::Sort, &["s"]),
+    (Action::Quit, &["Esc"]),
+];
+
+/// Maps keys to actions, configured through a `keybinds` block such as:
+///
+/// ```kdl
+/// keybinds {
+///     up "k" "Up" "Ctrl p"
+///     kill "d"
+/// }
+/// ```
+///
+/// Actions missing from the block keep their default keys.
+pub struct Keybinds {
+    binds: Vec<(KeyWithModifier, Action)>,
+}
+
+impl Default for Keybinds {
+    fn default() -> Self {
+        let binds = DEFAULT_KEYBINDS.iter()
+            .flat_map(|(action, keys)| keys.iter().map(move |key| (parse_key(key).expect("invalid default keybind"), *action)))
+            .collect();
+        Self { binds }
+    }
+}
+
+impl Keybinds {
+    pub fn parse(config: &str) -> Result<Self, String> {
+        let mut configured: Vec<(KeyWithModifier, Action)> = Vec::new();
+        for statement in tokenize(config) {
+            let (name, keys) = statement.split_first().ok_or("empty keybind")?;
+            let action = Action::from_str(name)?;
+            if keys.is_empty() {
+                return Err(format!("no key given for '{}'", name));
+            }
+            for key in keys {
+                configured.push((parse_key(key)?, action));
+            }
+        }
+        let defaults: Vec<(KeyWithModifier, Action)> = Keybinds::default().binds.into_iter()
+            .filter(|(_, action)| !configured.iter().any(|(_, a)| a == action))
+            .collect();
+        // configured keys come first so they win over the defaults they shadow
+        configured.extend(defaults);
+        Ok(Self { binds: configured })
+    }
+
+    pub fn action(&self, key: &KeyWithModifier) -> Option<Action> {
+        self.binds.iter().find(|(bind, _)| bind == key).map(|(_, action)| *action)
+    }
+
+    /// The first key bound to an action, to mention it in messages
+    pub fn key(&self, action: Action) -> Option<&KeyWithModifier> {
+        self.binds.iter().find(|(_, a)| *a == action).map(|(key, _)| key)
+    }
+}
+
+fn parse_key(key: &str) -> Result<KeyWithModifier, String> {
+    KeyWithModifier::from_str(key).map_err(|e| format!("invalid key '{}': {}", key, e))
+}
+
+/// Split a kdl block into statements of bare words and quoted strings
+fn tokenize(config: &str) -> Vec<Vec<String>> {
+    let mut statements = Vec::new();
+    let mut statement = Vec::new();
+    let mut chars = config.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' | ';' => statements.push(std::mem::take(&mut statement)),
+            '/' if chars.peek() == Some(&'/') => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        break;
+                    }
+                }
+                statements.push(std::mem::take(&mut statement));
+            }
+            '"' => {
+                let mut token = String::new();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '"' => break,
+                        '\\' => token.extend(chars.next()),
+                        _ => token.push(c),
+                    }
+                }
+                statement.push(token);
+            }
+            c if c.is_whitespace() => (),
+            c => {
+                let mut token = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && *c != ';' && *c != '"') {
+                    token.push(c);
+                }
+                statement.push(token);
+            }
+        }
+    }
+    statements.push(statement);
+    statements.into_iter().filter(|statement| !statement.is_empty()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(key: &str) -> KeyWithModifier {
+        parse_key(key).unwrap()
+    }
+
+    #[test]
+    fn tokenize_splits_statements_on_newlines_semicolons_and_comments() {
+        let config = "up \"k\" \"Ctrl p\"; kill d // the d key\n\n;;search \"a \\\"b\\\\\"";
+        assert_eq!(tokenize(config), [
+            vec!["up", "k", "Ctrl p"],
+            vec!["kill", "d"],
+            vec!["search", "a \"b\\"],
+        ]);
+        assert!(tokenize(" \n;// only a comment\n").is_empty());
+    }
+
+    #[test]
+    fn configured_keys_replace_the_defaults_of_their_action() {
+        let keybinds = Keybinds::parse("up \"Ctrl p\" \"Up\"\nkill \"d\"").unwrap();
+        assert_eq!(keybinds.action(&key("Ctrl p")), Some(Action::Up));
+        assert_eq!(keybinds.action(&key("Up")), Some(Action::Up));
+        assert_eq!(keybinds.action(&key("k")), None);
+        assert_eq!(keybinds.action(&key("p")), None);
+        assert_eq!(keybinds.action(&key("d")), Some(Action::Kill));
+        assert_eq!(keybinds.action(&key("x")), None);
+        // actions left out keep their default keys
+        assert_eq!(keybinds.action(&key("j")), Some(Action::Down));
+        assert_eq!(keybinds.action(&key("Esc")), Some(Action::Quit));
+        assert_eq!(keybinds.key(Action::Up), Some(&key("Ctrl p")));
+        assert_eq!(keybinds.key(Action::Undo), Some(&key("u")));
+    }
+
+    #[test]
+    fn configured_keys_win_over_the_defaults_they_shadow() {
+        let keybinds = Keybinds::parse("sort \"j\"").unwrap();
+        assert_eq!(keybinds.action(&key("j")), Some(Action::Sort));
+        assert_eq!(keybinds.action(&key("Down")), Some(Action::Down));
+    }
+
+    #[test]
+    fn invalid_keybinds_are_rejected() {
+        assert_eq!(Keybinds::parse("jump \"g\"").err(), Some("unknown action 'jump'".to_string()));
+        assert_eq!(Keybinds::parse("up").err(), Some("no key given for 'up'".to_string()));
+        assert!(Keybinds::parse("up \"Hyper k\"").is_err());
+        assert!(Keybinds::parse("").is_ok());
+    }
+}
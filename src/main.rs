@@ -1,129 +1,157 @@
+mod command;
 mod config;
+mod deadsession;
+mod dump;
+mod history;
+mod host;
+mod exitedgroup;
+#[cfg(test)]
+mod fixture;
+mod group;
+mod keybinds;
+mod newsession;
 mod pane;
+mod picker;
+mod preview;
 mod session;
 mod sessiontree;
+#[cfg(test)]
+mod snapshots;
+mod sort;
+mod status;
 mod tab;
 mod utils;
 
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, VecDeque};
+use std::time::{Duration, Instant};
 use zellij_tile::prelude::*;
 
+use command::Command;
 use config::Config;
-use sessiontree::SessionTree;
+use dump::DumpRequest;
+use history::History;
+use host::{Host, HostCommand};
+use keybinds::Action;
+use newsession::NewSession;
+use picker::{PickFormat, PickLevel, PickReply, PickRequest};
+use serde_json::{json, Value};
+use sessiontree::{NodeId, NodeKey, SessionTree};
+use status::Status;
+use sort::{sort_sessions, SortOrder};
+
+#[derive(Default)]
+enum Mode {
+    #[default]
+    Normal,
+    Search,
+    NewSession(NewSession),
+    ConfirmKill,
+    Rename,
+}
 
 #[derive(Default)]
 struct State {
     session_tree: SessionTree,
-    initialised: bool,
     config: Config,
+    mode: Mode,
+    layouts: Vec<LayoutInfo>,
+    history: History,
+    // last session update, kept to sort the tree again
+    sessions: Vec<SessionInfo>,
+    resurrectable: Vec<(String, Duration)>,
+    sort: SortOrder,
+    // kills waiting for their undo window to elapse, one per timer, None once undone
+    pending_kills: VecDeque<Option<Vec<NodeKey>>>,
 
-    handling_sessionpick_request_from: Option<(PipeSource, BTreeMap<String, String>)>,
-    debug: String,
+    // node and time of the last click, to detect double clicks, by key as the tree may be rebuilt in between
+    last_click: Option<(NodeKey, Instant)>,
+    pick_request: Option<PickRequest>,
+    // dump requests received before the first session update
+    pending_dumps: Vec<DumpRequest>,
+    // commands received before the first session update, run once it arrives
+    pending_commands: Vec<(Command, PipeSource)>,
+    initialised: bool,
+    status: Status,
+    host: Box<dyn Host>,
 }
 
+// the host shims only link on wasm, tests drive the state directly
+#[cfg(not(test))]
 register_plugin!(State);
 
+const DOUBLE_CLICK_DELAY: Duration = Duration::from_millis(500);
+
+/// "session 'foo'" for a single node, "3 nodes" otherwise
+fn describe_keys(keys: &[NodeKey]) -> String {
+    match keys {
+        [key] => key.to_string(),
+        _ => utils::pluralize(keys.len(), "node"),
+    }
+}
+
 impl ZellijPlugin for State {
     fn load(&mut self, configuration: BTreeMap<String, String>) {
         self.config = Config::from(configuration);
-        request_permission(&[
+        self.history = History::load(self.host.as_ref());
+        self.sort = self.config.sort;
+        for error in self.config.errors.iter() {
+            self.status.error(error.clone());
+        }
+        self.host.run(HostCommand::RequestPermission(vec![
             PermissionType::ChangeApplicationState,
             PermissionType::MessageAndLaunchOtherPlugins,
             PermissionType::ReadApplicationState,
             PermissionType::ReadCliPipes,
             PermissionType::RunCommands,
-        ]);
-        subscribe(&[EventType::SessionUpdate, EventType::Key]);
+        ]));
+        self.host.run(HostCommand::Subscribe(vec![EventType::SessionUpdate, EventType::Key, EventType::Timer, EventType::Mouse]));
     }
 
     fn update(&mut self, event: Event) -> bool {
         let mut should_render = false;
         match event {
-            Event::SessionUpdate(sessions, _) => {
-                if !self.initialised {
-                    self.session_tree = SessionTree::new(sessions, &self.config);
-                    self.initialised = true;
-                    should_render = true;
+            Event::SessionUpdate(sessions, resurrectable) => {
+                if let Some(current) = sessions.iter().find(|s| s.is_current_session) {
+                    self.layouts = current.available_layouts.clone();
+                }
+                self.sessions = sessions;
+                self.resurrectable = resurrectable;
+                self.rebuild_tree();
+                self.initialised = true;
+                for request in self.pending_dumps.drain(..) {
+                    request.reply(self.host.as_ref(), &self.session_tree);
                 }
+                for (command, source) in std::mem::take(&mut self.pending_commands) {
+                    let result = self.run_command(command);
+                    self.report_command(&source, result);
+                    if let PipeSource::Cli(pipe_id) = source {
+                        self.host.run(HostCommand::UnblockCliPipeInput(pipe_id));
+                    }
+                }
+                should_render = true;
             }
             Event::Key(key) => {
-                match key {
-                    // Select the node under the cursor
-                    KeyWithModifier {
-                        bare_key: BareKey::Enter,
-                        key_modifiers: _,
-                    } => {
-                        let _ = match self.handling_sessionpick_request_from {
-                            Some(_) => self.handle_sessionpick_request(),
-                            _ => self.session_tree.switch_to_selected(),
-                        };
-                    }
-                    // Move up, looping around
-                    KeyWithModifier {
-                        bare_key: BareKey::Char('k') | BareKey::Up,
-                        key_modifiers: _,
-                    } => {
-                        let _ = self.session_tree.handle_up();
-                        should_render = true;
-                    }
-                    // Move down, looping around
-                    KeyWithModifier {
-                        bare_key: BareKey::Char('j') | BareKey::Down,
-                        key_modifiers: _,
-                    } => {
-                        let _ = self.session_tree.handle_down();
-                        should_render = true;
-                    }
-                    // Collapse the current node, moving up if already collapsed
-                    KeyWithModifier {
-                        bare_key: BareKey::Char('h') | BareKey::Left,
-                        key_modifiers: _,
-                    } => {
-                        let _ = self.session_tree.handle_left();
-                        should_render = true;
-                    }
-                    // Expand the current node, moving down if already expanded
-                    KeyWithModifier {
-                        bare_key: BareKey::Char('l') | BareKey::Right,
-                        key_modifiers: _,
-                    } => {
-                        let _ = self.session_tree.handle_right();
-                        should_render = true;
-                    }
-                    // Kill the current node
-                    KeyWithModifier {
-                        bare_key: BareKey::Char('x') | BareKey::Delete,
-                        key_modifiers: _,
-                    } => {
-                        let _ = self.session_tree.kill_selected();
-                        self.initialised = false;
-                        should_render = true;
-                    }
-                    // Select the node at the given index
-                    KeyWithModifier {
-                        bare_key: BareKey::Char(c),
-                        key_modifiers: _,
-                    } => {
-                        if let Some(digit) = c.to_digit(10) {
-                            let _ = self.session_tree.switch_by_index(digit as usize);
-                        }
-                        // Capital letters are used to select the node at the given index
-                        else if c.is_ascii_uppercase() {
-                            let index = 10 + c as u8 - b'A';
-                            let _ = self.session_tree.switch_by_index(index as usize);
-                        }
-                        //hide_self();
-                        should_render = true;
-                    }
-                    // Quit
-                    KeyWithModifier {
-                        bare_key: BareKey::Esc,
-                        key_modifiers: _,
-                    } => {
-                        hide_self();
+                self.status.clear();
+                self.session_tree.reset_scroll();
+                should_render = match self.mode {
+                    Mode::Normal => self.handle_key(key),
+                    Mode::Search => self.handle_search_key(key),
+                    Mode::NewSession(_) => self.handle_new_session_key(key),
+                    Mode::ConfirmKill => self.handle_confirm_kill_key(key),
+                    Mode::Rename => self.handle_rename_key(key),
+                };
+            }
+            Event::Mouse(mouse) => {
+                should_render = self.handle_mouse(mouse);
+            }
+            Event::Timer(_) => {
+                if let Some(Some(keys)) = self.pending_kills.pop_front() {
+                    match self.session_tree.kill_nodes(self.host.as_ref(), &keys) {
+                        Ok(()) => self.status.info(format!("Killed {}", describe_keys(&keys))),
+                        Err(error) => self.status.error(error),
                     }
-                    _ => (),
                 }
+                should_render = true;
             }
             _ => (),
         };
@@ -131,53 +159,761 @@ impl ZellijPlugin for State {
     }
 
     fn pipe(&mut self, pipe_message: PipeMessage) -> bool {
-        if pipe_message.is_private && pipe_message.name == "sessionpicker" {
-            if let PipeSource::Cli(pipe_id) = &pipe_message.source {
-                self.debug = format!("Received sessionpicker request from cli pipe {}", pipe_id);
-                block_cli_pipe_input(pipe_id);
+        if pipe_message.is_private && DumpRequest::is_dump(&pipe_message.name) {
+            let request = DumpRequest::new(pipe_message.source, pipe_message.args);
+            match self.initialised {
+                true => request.reply(self.host.as_ref(), &self.session_tree),
+                false => {
+                    request.defer(self.host.as_ref());
+                    self.pending_dumps.push(request);
+                }
             }
-            self.handling_sessionpick_request_from = Some((pipe_message.source, pipe_message.args));
-            true
-        } else {
-            false
+            return false;
+        }
+        let payload = pipe_message.payload.as_deref();
+        let command = match pipe_message.is_private {
+            true => Command::from_pipe(&pipe_message.name, payload, &pipe_message.args),
+            false => None,
+        };
+        match (command, self.initialised) {
+            // the tree is empty until then, a cli pipe being kept open meanwhile
+            (Some(Ok(command)), false) => {
+                if let PipeSource::Cli(pipe_id) = &pipe_message.source {
+                    self.host.run(HostCommand::BlockCliPipeInput(pipe_id.clone()));
+                }
+                self.pending_commands.push((command, pipe_message.source));
+                return false;
+            }
+            (Some(command), _) => {
+                let result = command.and_then(|command| self.run_command(command));
+                self.report_command(&pipe_message.source, result);
+                return true;
+            }
+            (None, _) => (),
+        }
+        let level = PickLevel::from_pipe(&pipe_message.name, &pipe_message.args);
+        match (pipe_message.is_private, level) {
+            (true, Some(Ok(level))) => {
+                if let PipeSource::Cli(pipe_id) = &pipe_message.source {
+                    self.status.info(format!("Received {} request from cli pipe {}", pipe_message.name, pipe_id));
+                }
+                if let Some(previous) = self.pick_request.take() {
+                    previous.reply(self.host.as_ref(), PickReply::Error("superseded by another picker request".to_string()));
+                }
+                self.pick_request = Some(PickRequest::new(self.host.as_ref(), pipe_message.name, level, pipe_message.source, pipe_message.args));
+                true
+            }
+            // rejected right away, the cli getting the error like for commands
+            (true, Some(Err(error))) => {
+                match &pipe_message.source {
+                    PipeSource::Cli(pipe_id) => self.host.run(HostCommand::CliPipeOutput(pipe_id.clone(), error.clone())),
+                    _ => PickRequest::new(self.host.as_ref(), pipe_message.name, PickLevel::default(), pipe_message.source, pipe_message.args)
+                        .reply(self.host.as_ref(), PickReply::Error(error.clone())),
+                }
+                self.status.error(error);
+                true
+            }
+            _ => false,
         }
     }
 
     fn render(&mut self, rows: usize, cols: usize) {
         println!();
-        if !self.debug.is_empty() {
-            println!("{}", self.debug);
+        let mut header_rows = 2 + self.status.render();
+        println!();
+        if matches!(self.mode, Mode::Search) || !self.session_tree.query().is_empty() {
+            println!("/{}", self.session_tree.query());
+            header_rows += 1;
+        }
+        if let Mode::NewSession(prompt) = &self.mode {
+            prompt.render();
             println!();
+            header_rows += 3;
+        }
+        let pending: Vec<String> = self.pending_kills.iter()
+            .flatten()
+            .flatten()
+            .filter_map(|key| self.session_tree.find(key))
+            .map(|id| self.session_tree.node(id).name())
+            .collect();
+        if !pending.is_empty() {
+            match self.config.keybinds.key(Action::Undo) {
+                Some(key) => println!("Killing {}, press {} to undo", pending.join(", "), key),
+                None => println!("Killing {}", pending.join(", ")),
+            }
+            header_rows += 1;
         }
-        self.session_tree.render(rows.saturating_sub(3), cols);
+        self.session_tree.render(self.host.as_ref(), header_rows, rows.saturating_sub(header_rows), cols);
     }
 }
 
 impl State {
+    fn handle_key(&mut self, key: KeyWithModifier) -> bool {
+        let mut should_render = true;
+        // Keys of the hint alphabet, none of them being bound to an action, select the node labelled with them
+        if let BareKey::Char(c) = key.bare_key {
+            if key.has_no_modifiers() && self.config.hint_alphabet.contains(c) {
+                let result = match (self.session_tree.type_hint(c), &self.pick_request) {
+                    (Ok(Some(index)), Some(_)) => self.session_tree.select_by_index(index)
+                        .and_then(|_| self.handle_sessionpick_request()),
+                    (Ok(Some(index)), None) => self.session_tree.switch_by_index(self.host.as_ref(), index)
+                        .and_then(|session| self.record_switch(session)),
+                    (result, _) => result.map(|_| ()),
+                };
+                self.status.report(result);
+                return true;
+            }
+        }
+        // Any other key abandons a hint being typed
+        if self.session_tree.clear_hint() {
+            return true;
+        }
+        match self.config.keybinds.action(&key) {
+            // Select the node under the cursor
+            Some(Action::Select) => {
+                let result = match self.pick_request {
+                    Some(_) => self.handle_sessionpick_request(),
+                    _ => self.session_tree.switch_to_selected(self.host.as_ref()).and_then(|session| self.record_switch(session)),
+                };
+                self.status.report(result);
+            }
+            // Move up, looping around
+            Some(Action::Up) => {
+                let result = self.session_tree.handle_up();
+                self.status.report(result);
+            }
+            // Move down, looping around
+            Some(Action::Down) => {
+                let result = self.session_tree.handle_down();
+                self.status.report(result);
+            }
+            // Collapse the current node, moving up if already collapsed
+            Some(Action::Collapse) => {
+                let result = self.session_tree.handle_left();
+                self.status.report(result);
+            }
+            // Expand the current node, moving down if already expanded
+            Some(Action::Expand) => {
+                let result = self.session_tree.handle_right();
+                self.status.report(result);
+            }
+            // Kill the current node
+            Some(Action::Kill) => {
+                if self.config.confirm_kill {
+                    match self.session_tree.describe_kill() {
+                        Ok(description) => {
+                            self.session_tree.set_prompt(Some(format!("{} (y/n)", description)));
+                            self.mode = Mode::ConfirmKill;
+                        }
+                        Err(error) => self.status.error(error),
+                    }
+                } else {
+                    let result = self.stage_kill();
+                    self.status.report(result);
+                }
+            }
+            // Undo the last pending kill
+            Some(Action::Undo) => {
+                match self.pending_kills.iter_mut().rev().find(|keys| keys.is_some()) {
+                    Some(pending) => {
+                        if let Some(keys) = pending.take() {
+                            self.status.info(format!("Cancelled kill of {}", describe_keys(&keys)));
+                        }
+                    }
+                    None => self.status.error("nothing to undo".to_string()),
+                }
+            }
+            // Mark the current node for batch actions
+            Some(Action::Mark) => {
+                let result = self.session_tree.toggle_mark();
+                self.status.report(result);
+            }
+            // Cycle through the sort orders
+            Some(Action::Sort) => {
+                self.sort = self.sort.next();
+                self.rebuild_tree();
+                self.status.info(format!("Sorted by {}", self.sort));
+            }
+            // Switch back to the previous session
+            Some(Action::LastSession) => {
+                let result = self.switch_to_last_session();
+                self.status.report(result);
+            }
+            // Filter the tree
+            Some(Action::Search) => self.mode = Mode::Search,
+            // Show or hide the recent messages
+            Some(Action::Messages) => self.status.toggle_log(),
+            // Rename the current node
+            Some(Action::Rename) => {
+                match self.session_tree.start_rename() {
+                    Ok(()) => self.mode = Mode::Rename,
+                    Err(error) => self.status.error(error),
+                }
+            }
+            // Create a new session
+            Some(Action::NewSession) => self.mode = Mode::NewSession(NewSession::default()),
+            // Quit, clearing the filter and marks first if there are any
+            Some(Action::Quit) => {
+                if !self.session_tree.query().is_empty() {
+                    self.session_tree.set_query(String::new());
+                } else if self.session_tree.has_marks() {
+                    self.session_tree.clear_marks();
+                } else if let Some(request) = self.pick_request.take() {
+                    request.reply(self.host.as_ref(), PickReply::Cancelled);
+                    self.host.run(HostCommand::CloseSelf);
+                } else {
+                    self.host.run(HostCommand::HideSelf);
+                    should_render = false;
+                }
+            }
+            None => should_render = false,
+        }
+        should_render
+    }
+
+    fn handle_mouse(&mut self, mouse: Mouse) -> bool {
+        // prompts and text fields are only driven by the keyboard
+        if !matches!(self.mode, Mode::Normal) {
+            return false;
+        }
+        match mouse {
+            Mouse::ScrollUp(lines) => self.session_tree.scroll(-(lines as isize)),
+            Mouse::ScrollDown(lines) => self.session_tree.scroll(lines as isize),
+            Mouse::LeftClick(line, column) => {
+                let Some(id) = self.session_tree.node_at(line) else {
+                    return false;
+                };
+                let key = self.session_tree.node(id).key();
+                let is_double_click = self.last_click
+                    .as_ref()
+                    .is_some_and(|(last, at)| *last == key && at.elapsed() < DOUBLE_CLICK_DELAY);
+                self.last_click = Some((key, Instant::now()));
+                if self.session_tree.is_fold_marker(id, column) {
+                    self.status.clear();
+                    self.session_tree.toggle_fold(id);
+                } else if is_double_click {
+                    self.last_click = None;
+                    let result = match self.pick_request {
+                        Some(_) => self.handle_sessionpick_request(),
+                        _ => self.session_tree.switch_to_selected(self.host.as_ref()).and_then(|session| self.record_switch(session)),
+                    };
+                    self.status.report(result);
+                } else {
+                    self.status.clear();
+                    self.session_tree.select(id);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    fn handle_search_key(&mut self, key: KeyWithModifier) -> bool {
+        let mut query = self.session_tree.query().to_string();
+        match key.bare_key {
+            // Keep the filter and go back to navigating
+            BareKey::Enter => self.mode = Mode::Normal,
+            // Drop the filter
+            BareKey::Esc => {
+                query.clear();
+                self.mode = Mode::Normal;
+            }
+            BareKey::Backspace => {
+                query.pop();
+            }
+            BareKey::Up => {
+                let result = self.session_tree.handle_up();
+                self.status.report(result);
+            }
+            BareKey::Down => {
+                let result = self.session_tree.handle_down();
+                self.status.report(result);
+            }
+            BareKey::Char(c) => query.push(c),
+            _ => return false,
+        }
+        if query != self.session_tree.query() {
+            self.session_tree.set_query(query);
+        }
+        true
+    }
+
+    fn handle_new_session_key(&mut self, key: KeyWithModifier) -> bool {
+        let Mode::NewSession(prompt) = &mut self.mode else {
+            return false;
+        };
+        match key.bare_key {
+            BareKey::Esc => self.mode = Mode::Normal,
+            BareKey::Enter => {
+                let name = prompt.name().to_string();
+                let layout = prompt.layout(&self.layouts);
+                self.mode = Mode::Normal;
+                let result = self.create_session(name, layout);
+                self.status.report(result);
+            }
+            _ => prompt.handle_key(key, &self.layouts),
+        }
+        true
+    }
+
+    fn handle_confirm_kill_key(&mut self, key: KeyWithModifier) -> bool {
+        if let BareKey::Char('y') = key.bare_key {
+            let result = self.stage_kill();
+            self.status.report(result);
+        }
+        self.session_tree.set_prompt(None);
+        self.mode = Mode::Normal;
+        true
+    }
+
+    fn handle_rename_key(&mut self, key: KeyWithModifier) -> bool {
+        match key.bare_key {
+            BareKey::Enter => {
+                match self.session_tree.finish_rename(self.host.as_ref()) {
+                    Ok(()) => self.status.info("Renamed".to_string()),
+                    Err(error) => self.status.error(error),
+                }
+                self.mode = Mode::Normal;
+            }
+            BareKey::Esc => {
+                self.session_tree.cancel_rename();
+                self.mode = Mode::Normal;
+            }
+            BareKey::Backspace => {
+                if let Some(name) = self.session_tree.editing_mut() {
+                    name.pop();
+                }
+            }
+            BareKey::Char(c) => {
+                if let Some(name) = self.session_tree.editing_mut() {
+                    name.push(c);
+                }
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Run a command received through a pipe
+    fn run_command(&mut self, command: Command) -> Result<(), String> {
+        match command {
+            Command::NextSession => {
+                let session = self.session_tree.cycle_session(self.host.as_ref(), 1)?;
+                self.record_switch(session)
+            }
+            Command::PrevSession => {
+                let session = self.session_tree.cycle_session(self.host.as_ref(), -1)?;
+                self.record_switch(session)
+            }
+            Command::LastSession => self.switch_to_last_session(),
+            Command::Switch(name) => {
+                let key = self.find_session(name)?;
+                let session = self.session_tree.focus_node(self.host.as_ref(), &key)?;
+                self.record_switch(session)
+            }
+            Command::FocusTab(session, position) => {
+                let session = self.session_tree.focus_node(self.host.as_ref(), &NodeKey::Tab(session, position))?;
+                self.record_switch(session)
+            }
+            Command::Kill(name) => {
+                let key = self.find_session(name)?;
+                self.session_tree.kill_nodes(self.host.as_ref(), std::slice::from_ref(&key))?;
+                self.status.info(format!("Killed {}", key));
+                Ok(())
+            }
+            Command::ExpandAll => {
+                self.session_tree.expand_all();
+                Ok(())
+            }
+        }
+    }
+
+    /// Sort the last session update and rebuild the tree from it
+    fn rebuild_tree(&mut self) {
+        // other sessions' plugins may have switched since
+        if self.sort == SortOrder::Mru {
+            self.history = History::load(self.host.as_ref());
+        }
+        let mut sessions = self.sessions.clone();
+        sort_sessions(&mut sessions, self.sort, &self.history);
+        self.session_tree.update(sessions, self.resurrectable.clone(), &self.config);
+    }
+
+    /// Remember a switch made through the plugin, for the mru order and the last session key
+    fn record_switch(&mut self, session: String) -> Result<(), String> {
+        match self.session_tree.current_session() {
+            Some(current) if current != session => self.history.record(self.host.as_ref(), &current, &session),
+            _ => Ok(()),
+        }
+    }
+
+    /// Show the result of a command, errors being printed by the cli too
+    fn report_command(&mut self, source: &PipeSource, result: Result<(), String>) {
+        if let (Err(error), PipeSource::Cli(pipe_id)) = (&result, source) {
+            self.host.run(HostCommand::CliPipeOutput(pipe_id.clone(), error.clone()));
+        }
+        self.status.report(result);
+    }
+
+    /// Switch back to the previous session, like tmux's `L`
+    fn switch_to_last_session(&mut self) -> Result<(), String> {
+        let current = self.session_tree.current_session().unwrap_or_default();
+        self.history = History::load(self.host.as_ref());
+        let last = self.history.last(&current).ok_or("no previous session")?.to_string();
+        let key = self.find_session(last)?;
+        // picked instead while a picker request waits for a reply
+        if self.pick_request.is_some() {
+            return self.pick(vec![key], false);
+        }
+        let session = self.session_tree.focus_node(self.host.as_ref(), &key)?;
+        self.record_switch(session)
+    }
+
+    /// The live or exited session with that name
+    fn find_session(&self, name: String) -> Result<NodeKey, String> {
+        let live = NodeKey::Session(name.clone());
+        if self.session_tree.find(&live).is_some() {
+            return Ok(live);
+        }
+        let dead = NodeKey::DeadSession(name.clone());
+        match self.session_tree.find(&dead) {
+            Some(_) => Ok(dead),
+            None => Err(format!("no session named '{}'", name)),
+        }
+    }
+
+    /// Kill the marked or selected nodes, after the undo window if one is configured
+    fn stage_kill(&mut self) -> Result<(), String> {
+        let keys = self.session_tree.targets()?;
+        self.session_tree.clear_marks();
+        if self.config.undo_delay <= 0.0 {
+            self.session_tree.kill_nodes(self.host.as_ref(), &keys)?;
+            self.status.info(format!("Killed {}", describe_keys(&keys)));
+            return Ok(());
+        }
+        self.pending_kills.push_back(Some(keys));
+        self.host.run(HostCommand::SetTimeout(self.config.undo_delay));
+        Ok(())
+    }
+
+    fn create_session(&mut self, name: String, layout: Option<LayoutInfo>) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("session name cannot be empty".to_string());
+        }
+        let format = match &self.pick_request {
+            Some(request) if request.level() != PickLevel::Session => {
+                return Err(format!("pick a {} instead of creating a session", request.level()));
+            }
+            Some(request) => Some(request.format()),
+            None => None,
+        };
+        match layout {
+            Some(layout) => self.host.run(HostCommand::SwitchSessionWithLayout(name.clone(), layout)),
+            None => self.host.run(HostCommand::SwitchSession(name.clone())),
+        }
+        // the session is created and switched to either way, a picker then replies with its name
+        match format {
+            Some(PickFormat::Text) => self.send_sessionpick_response(name.clone()),
+            Some(PickFormat::Json) => self.send_sessionpick_response(json!({
+                "session": name,
+                "is_current": true,
+                "is_new": true,
+                "path": [name],
+            }).to_string()),
+            None => self.host.run(HostCommand::HideSelf),
+        }
+        self.record_switch(name)
+    }
+
     fn handle_sessionpick_request(&mut self) -> Result<(), String> {
-        let current_node = self.session_tree.get_current_node()?;
-        let session = self
-            .session_tree
-            .get_session(current_node.borrow().index())?;
-        let response = session.borrow().identifier();
-
-        match &self.handling_sessionpick_request_from {
-            Some((PipeSource::Plugin(plugin_id), args)) => {
-                pipe_message_to_plugin(
-                    MessageToPlugin::new("sessionpicker_result")
-                        .with_destination_plugin_id(*plugin_id)
-                        .with_args(args.clone())
-                        .with_payload(response),
-                );
-                close_self();
-            }
-            Some((PipeSource::Cli(pipe_id), _args)) => {
-                cli_pipe_output(pipe_id, &response);
-                unblock_cli_pipe_input(pipe_id);
-                close_self();
-            }
-            _ => {}
+        let keys = self.session_tree.targets()?;
+        self.pick(keys, self.session_tree.has_marks())
+    }
+
+    /// Reply to the picker request with the nodes, as a JSON array if `is_list` even when there is
+    /// a single one
+    fn pick(&mut self, keys: Vec<NodeKey>, is_list: bool) -> Result<(), String> {
+        let (level, format) = match &self.pick_request {
+            Some(request) => (request.level(), request.format()),
+            None => return Err("no pending picker request".to_string()),
+        };
+        let mut picked: Vec<NodeId> = Vec::new();
+        for key in keys {
+            let id = self.session_tree.find(&key).ok_or("node no longer exists")?;
+            let id = self
+                .session_tree
+                .find_ancestor(id, |key| level.accepts(key))
+                .ok_or(format!("select a {}", level))?;
+            if !picked.contains(&id) {
+                picked.push(id);
+            }
+        }
+        let response = match format {
+            PickFormat::Text => picked.iter()
+                .map(|&id| level.identifier(self.session_tree.node(id)))
+                .collect::<Vec<String>>()
+                .join("\n"),
+            PickFormat::Json => {
+                let nodes: Vec<Value> = picked.iter()
+                    .map(|&id| self.session_tree.to_json(id))
+                    .collect();
+                // a single object unless several nodes were marked
+                match (is_list, nodes.as_slice()) {
+                    (false, [node]) => node.to_string(),
+                    _ => Value::Array(nodes).to_string(),
+                }
+            }
         };
+        self.send_sessionpick_response(response);
         Ok(())
     }
+
+    fn send_sessionpick_response(&mut self, response: String) {
+        if let Some(request) = self.pick_request.take() {
+            request.reply(self.host.as_ref(), PickReply::Picked(response));
+            self.host.run(HostCommand::CloseSelf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use host::RecordingHost;
+    use sessiontree::tests::session;
+
+    fn state() -> (State, RecordingHost) {
+        let host = RecordingHost::default();
+        let mut state = State {
+            host: Box::new(host.clone()),
+            ..Default::default()
+        };
+        state.load(BTreeMap::new());
+        host.take();
+        (state, host)
+    }
+
+    fn session_update(state: &mut State) {
+        let sessions = vec![
+            session("alpha", true, &[("editor", &["vim"])]),
+            session("beta", false, &[("build", &["cargo"])]),
+        ];
+        state.update(Event::SessionUpdate(sessions, Vec::new()));
+    }
+
+    fn pipe(state: &mut State, source: PipeSource, name: &str, payload: Option<&str>) {
+        let payload = payload.map(|payload| payload.to_string());
+        state.pipe(PipeMessage::new(source, name, &payload, &None, true));
+    }
+
+    fn press(state: &mut State, key: BareKey) {
+        state.update(Event::Key(KeyWithModifier::new(key)));
+    }
+
+    fn cli(pipe_id: &str) -> PipeSource {
+        PipeSource::Cli(pipe_id.to_string())
+    }
+
+    #[test]
+    fn load_subscribes_to_updates() {
+        let host = RecordingHost::default();
+        let mut state = State {
+            host: Box::new(host.clone()),
+            ..Default::default()
+        };
+        state.load(BTreeMap::new());
+        assert!(host.take().iter().any(|command| matches!(command, HostCommand::Subscribe(event_types) if event_types.contains(&EventType::SessionUpdate))));
+    }
+
+    #[test]
+    fn tab_switches_back_to_the_previous_session() {
+        let (mut state, host) = state();
+        session_update(&mut state);
+        History::default().record(&host, "beta", "alpha").unwrap();
+        press(&mut state, BareKey::Tab);
+        assert_eq!(host.take(), [HostCommand::SwitchSession("beta".to_string()), HostCommand::HideSelf]);
+    }
+
+    #[test]
+    fn clicks_on_different_nodes_of_a_line_are_not_a_double_click() {
+        let (mut state, host) = state();
+        session_update(&mut state);
+        state.render(20, 50);
+        let line = (0..20)
+            .find(|&line| state.session_tree.node_at(line).is_some_and(|id| state.session_tree.node(id).name() == "beta"))
+            .unwrap();
+        state.update(Event::Mouse(Mouse::LeftClick(line, 10)));
+        // gamma takes the place of beta before the second click
+        let sessions = vec![session("alpha", true, &[("editor", &["vim"])]), session("gamma", false, &[])];
+        state.update(Event::SessionUpdate(sessions, Vec::new()));
+        state.render(20, 50);
+        state.update(Event::Mouse(Mouse::LeftClick(line, 10)));
+        assert_eq!(host.take(), []);
+        state.update(Event::Mouse(Mouse::LeftClick(line, 10)));
+        assert_eq!(host.take(), [HostCommand::SwitchSession("gamma".to_string()), HostCommand::HideSelf]);
+    }
+
+    #[test]
+    fn cli_picker_prints_the_picked_session() {
+        let (mut state, host) = state();
+        session_update(&mut state);
+        pipe(&mut state, cli("1"), "sessionpicker", None);
+        press(&mut state, BareKey::Down);
+        press(&mut state, BareKey::Enter);
+        assert_eq!(host.take(), [
+            HostCommand::BlockCliPipeInput("1".to_string()),
+            HostCommand::CliPipeOutput("1".to_string(), "beta".to_string()),
+            HostCommand::UnblockCliPipeInput("1".to_string()),
+            HostCommand::CloseSelf,
+        ]);
+    }
+
+    #[test]
+    fn quick_select_picks_instead_of_switching() {
+        let (mut state, host) = state();
+        session_update(&mut state);
+        state.render(20, 50);
+        pipe(&mut state, cli("1"), "sessionpicker", None);
+        press(&mut state, BareKey::Char('1'));
+        assert_eq!(host.take(), [
+            HostCommand::BlockCliPipeInput("1".to_string()),
+            HostCommand::CliPipeOutput("1".to_string(), "beta".to_string()),
+            HostCommand::UnblockCliPipeInput("1".to_string()),
+            HostCommand::CloseSelf,
+        ]);
+    }
+
+    #[test]
+    fn last_session_is_picked_instead_of_switched_to() {
+        let (mut state, host) = state();
+        session_update(&mut state);
+        History::default().record(&host, "beta", "alpha").unwrap();
+        pipe(&mut state, cli("1"), "sessionpicker", None);
+        press(&mut state, BareKey::Tab);
+        assert_eq!(host.take(), [
+            HostCommand::BlockCliPipeInput("1".to_string()),
+            HostCommand::CliPipeOutput("1".to_string(), "beta".to_string()),
+            HostCommand::UnblockCliPipeInput("1".to_string()),
+            HostCommand::CloseSelf,
+        ]);
+        assert!(state.pick_request.is_none());
+    }
+
+    #[test]
+    fn new_session_is_created_before_being_picked() {
+        let (mut state, host) = state();
+        session_update(&mut state);
+        pipe(&mut state, cli("1"), "sessionpicker", None);
+        press(&mut state, BareKey::Char('n'));
+        for c in "new".chars() {
+            press(&mut state, BareKey::Char(c));
+        }
+        press(&mut state, BareKey::Enter);
+        assert_eq!(host.take(), [
+            HostCommand::BlockCliPipeInput("1".to_string()),
+            HostCommand::SwitchSession("new".to_string()),
+            HostCommand::CliPipeOutput("1".to_string(), "new".to_string()),
+            HostCommand::UnblockCliPipeInput("1".to_string()),
+            HostCommand::CloseSelf,
+        ]);
+    }
+
+    #[test]
+    fn plugin_picker_is_told_when_cancelled() {
+        let (mut state, host) = state();
+        session_update(&mut state);
+        pipe(&mut state, PipeSource::Plugin(7), "tabpicker", None);
+        press(&mut state, BareKey::Esc);
+        let args = BTreeMap::from([("status".to_string(), "cancelled".to_string())]);
+        assert_eq!(host.take(), [
+            HostCommand::MessageToPlugin { name: "tabpicker_result".to_string(), plugin_id: 7, args, payload: None },
+            HostCommand::CloseSelf,
+        ]);
+    }
+
+    #[test]
+    fn superseded_picker_gets_an_error() {
+        let (mut state, host) = state();
+        pipe(&mut state, PipeSource::Plugin(7), "sessionpicker", None);
+        pipe(&mut state, cli("2"), "sessionpicker", None);
+        let args = BTreeMap::from([("status".to_string(), "error".to_string())]);
+        assert_eq!(host.take(), [
+            HostCommand::MessageToPlugin {
+                name: "sessionpicker_result".to_string(),
+                plugin_id: 7,
+                args,
+                payload: Some("superseded by another picker request".to_string()),
+            },
+            HostCommand::BlockCliPipeInput("2".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn only_picker_names_take_a_level() {
+        let (mut state, host) = state();
+        let level = |level: &str| BTreeMap::from([("level".to_string(), level.to_string())]);
+        state.pipe(PipeMessage::new(cli("8"), "foo", &None, &Some(level("tab")), true));
+        assert_eq!(host.take(), []);
+        state.pipe(PipeMessage::new(cli("9"), "sessionpicker", &None, &Some(level("panes")), true));
+        state.pipe(PipeMessage::new(PipeSource::Plugin(7), "tabpicker", &None, &Some(level("panes")), true));
+        let error = "unknown level 'panes', expected session, tab or pane".to_string();
+        let mut args = level("panes");
+        args.insert("status".to_string(), "error".to_string());
+        assert_eq!(host.take(), [
+            HostCommand::CliPipeOutput("9".to_string(), error.clone()),
+            HostCommand::MessageToPlugin { name: "tabpicker_result".to_string(), plugin_id: 7, args, payload: Some(error) },
+        ]);
+        assert!(state.pick_request.is_none());
+    }
+
+    #[test]
+    fn dump_waits_for_the_first_session_update() {
+        let (mut state, host) = state();
+        pipe(&mut state, cli("3"), "dump", None);
+        assert_eq!(host.take(), [HostCommand::BlockCliPipeInput("3".to_string())]);
+        session_update(&mut state);
+        assert_eq!(host.take(), [
+            HostCommand::CliPipeOutput("3".to_string(), [
+                "(0) alpha (focused)",
+                "  editor (focused)",
+                "    vim (focused)",
+                "(1) beta",
+                "  build (focused)",
+                "    cargo (focused)",
+            ].join("\n")),
+            HostCommand::UnblockCliPipeInput("3".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn commands_wait_for_the_first_session_update() {
+        let (mut state, host) = state();
+        pipe(&mut state, cli("3"), "next-session", None);
+        pipe(&mut state, cli("4"), "switch", Some("delta"));
+        assert_eq!(host.take(), [
+            HostCommand::BlockCliPipeInput("3".to_string()),
+            HostCommand::BlockCliPipeInput("4".to_string()),
+        ]);
+        session_update(&mut state);
+        assert_eq!(host.take(), [
+            HostCommand::SwitchSession("beta".to_string()),
+            HostCommand::HideSelf,
+            HostCommand::UnblockCliPipeInput("3".to_string()),
+            HostCommand::CliPipeOutput("4".to_string(), "no session named 'delta'".to_string()),
+            HostCommand::UnblockCliPipeInput("4".to_string()),
+        ]);
+    }
+
+    #[test]
+    fn commands_report_errors_to_the_cli() {
+        let (mut state, host) = state();
+        session_update(&mut state);
+        pipe(&mut state, cli("4"), "switch", Some("beta"));
+        pipe(&mut state, cli("5"), "switch", Some("delta"));
+        assert_eq!(host.take(), [
+            HostCommand::SwitchSession("beta".to_string()),
+            HostCommand::HideSelf,
+            HostCommand::CliPipeOutput("5".to_string(), "no session named 'delta'".to_string()),
+        ]);
+    }
 }
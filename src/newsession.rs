@@ -0,0 +1,79 @@
+use zellij_tile::prelude::*;
+
+#[derive(Default)]
+enum Field {
+    #[default]
+    Name,
+    Layout,
+}
+
+/// Inline prompt used to create a new session, optionally from a layout
+#[derive(Default)]
+pub struct NewSession {
+    name: String,
+    layout: String,
+    field: Field,
+    layout_index: Option<usize>,
+}
+
+impl NewSession {
+    pub fn name(&self) -> &str {
+        self.name.trim()
+    }
+
+    /// The layout picked from the available layouts, or a layout file path typed by the user
+    pub fn layout(&self, layouts: &[LayoutInfo]) -> Option<LayoutInfo> {
+        let layout = self.layout.trim();
+        if layout.is_empty() {
+            return None;
+        }
+        match layouts.iter().find(|l| l.name() == layout) {
+            Some(found) => Some(found.clone()),
+            None => Some(LayoutInfo::File(layout.to_string())),
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyWithModifier, layouts: &[LayoutInfo]) {
+        match (key.bare_key, &self.field) {
+            (BareKey::Tab, Field::Name) => self.field = Field::Layout,
+            (BareKey::Tab, Field::Layout) => self.field = Field::Name,
+            (BareKey::Backspace, Field::Name) => {
+                self.name.pop();
+            }
+            (BareKey::Backspace, Field::Layout) => {
+                self.layout.pop();
+                self.layout_index = None;
+            }
+            (BareKey::Char(c), Field::Name) => self.name.push(c),
+            (BareKey::Char(c), Field::Layout) => {
+                self.layout.push(c);
+                self.layout_index = None;
+            }
+            // Cycle through the available layouts
+            (BareKey::Up | BareKey::Down, Field::Layout) if !layouts.is_empty() => {
+                let index = match (self.layout_index, key.bare_key) {
+                    (None, _) => 0,
+                    (Some(i), BareKey::Down) => (i + 1) % layouts.len(),
+                    (Some(i), _) => (i + layouts.len() - 1) % layouts.len(),
+                };
+                self.layout_index = Some(index);
+                self.layout = layouts[index].name().to_string();
+            }
+            _ => (),
+        }
+    }
+
+    /// Render the prompt on two lines
+    pub fn render(&self) {
+        let name = format!("New session: {}", self.name);
+        let layout = format!("Layout (Up/Down to pick, or a path): {}", self.layout);
+        let (name, layout) = match self.field {
+            Field::Name => (format!("{}_", name), layout),
+            Field::Layout => (name, format!("{}_", layout)),
+        };
+        print_text(Text::new(&name).color_range(2, 0..12));
+        println!();
+        print_text(Text::new(&layout).color_range(2, 0..6));
+        println!();
+    }
+}
@@ -1,84 +1,102 @@
 use zellij_tile::prelude::*;
-use std::rc::Rc;
-use std::cell::RefCell;
+use serde_json::{json, Value};
 
-use crate::sessiontree::Node;
+use crate::host::{Host, HostCommand};
+use crate::preview::PaneGeometry;
+use crate::sessiontree::{Node, NodeKey};
+use crate::tab::Tab;
+use crate::utils::{highlight_mark, highlight_matches};
 
 pub struct Pane {
-    index: usize,
     title: String,
     is_focused: bool,
     pane_id: (u32, bool), //(id, is_plugin)
-    tab: Rc<RefCell<dyn Node>>,
-    shown: bool,
+    geometry: PaneGeometry,
+    command: Option<String>,
+    session: String,
+    is_current_session: bool,
+    tab_position: usize,
 }
 
 impl Pane {
-    pub fn new(index: usize, title: String, pane_id: (u32, bool), tab: Rc<RefCell<dyn Node>>, is_focused: bool) -> Self {
+    pub fn new(title: String, pane_id: (u32, bool), geometry: PaneGeometry, command: Option<String>, tab: &Tab, is_focused: bool) -> Self {
         Self {
-            index,
             title,
             is_focused,
             pane_id,
-            tab: tab.clone(),
-            shown: false,
+            geometry,
+            command,
+            session: tab.session().to_string(),
+            is_current_session: tab.is_current_session(),
+            tab_position: tab.position(),
         }
     }
 }
 
 impl Node for Pane {
-    fn index(&self) -> usize {
-        self.index
-    }
     fn identifier(&self) -> String {
         self.pane_id.0.to_string()
     }
+    fn key(&self) -> NodeKey {
+        NodeKey::Pane(self.session.clone(), self.pane_id)
+    }
+    fn name(&self) -> String {
+        self.title.clone()
+    }
     fn is_focused(&self) -> bool {
         self.is_focused
     }
-    fn focus(&self) -> Result<(), String> {
-        let tab = self.tab.borrow();
-        let tab_position = tab.identifier().parse().map_err(|_| "tab identifier is not a number")?;
-        let session = tab.parent().ok_or("tab has no parent")?;
-        let session_name = session.borrow().identifier();
-        if session.borrow().is_focused() {
-            focus_terminal_pane(self.pane_id.0, true);
+    fn focus(&self, host: &dyn Host) -> Result<(), String> {
+        if self.is_current_session {
+            host.run(HostCommand::FocusTerminalPane(self.pane_id.0));
         } else {
-            switch_session_with_focus(&session_name, Some(tab_position), Some(self.pane_id));
+            host.run(HostCommand::SwitchSessionWithFocus(self.session.clone(), Some(self.tab_position), Some(self.pane_id)));
         }
-        hide_self();
+        host.run(HostCommand::HideSelf);
         Ok(())
     }
-    fn kill(&self) -> Result<(), String> {
-        Err("cannot kill pane".to_string())
-    }
-    fn parent(&self) -> Option<Rc<RefCell<dyn Node>>> {
-        Some(self.tab.clone())
-    }
-    fn add_child(&mut self, _child: Rc<RefCell<dyn Node>>) {
-    }
-    fn is_shown(&self) -> bool {
-        self.shown
-    }
-    fn show(&mut self) {
-        self.shown = true;
+    fn kill(&self, host: &dyn Host) -> Result<(), String> {
+        // pane ids are only unique within a session
+        if !self.is_current_session {
+            return Err("cannot close a pane in another session".to_string());
+        }
+        host.run(HostCommand::ClosePane(self.pane_id));
+        Ok(())
     }
-    fn hide(&mut self) {
-        self.shown = false
+    fn can_rename(&self) -> Result<(), String> {
+        match self.is_current_session {
+            true => Ok(()),
+            false => Err("can only rename panes in the current session".to_string()),
+        }
     }
-    fn is_expanded(&self) -> bool {
-        true
+    fn rename(&self, host: &dyn Host, name: &str) -> Result<(), String> {
+        self.can_rename()?;
+        host.run(HostCommand::RenamePane(self.pane_id, name.to_string()));
+        Ok(())
     }
-    fn expand(&mut self) {
+    fn geometry(&self) -> Option<PaneGeometry> {
+        Some(self.geometry)
     }
-    fn collapse(&mut self) {
+    // no `cwd`, zellij's PaneInfo does not report the working directory of panes
+    fn to_json(&self) -> Value {
+        json!({
+            "pane": {
+                "id": self.pane_id.0,
+                "title": self.title,
+                "is_plugin": self.pane_id.1,
+                "is_focused": self.is_focused,
+                "command": self.command,
+            },
+        })
     }
-    fn render(&self, keybind: String, is_selected: bool) -> NestedListItem {
-        let text = format!("({0}) {1}", keybind, self.title);
-        let text_len = text.len();
-        match is_selected {
-            true => NestedListItem::new(text).indent(2).color_range(0, 0..text_len).selected(),
-            false => NestedListItem::new(text).indent(2),
-        }
+    fn render(&self, prefix: &str, is_selected: bool, is_marked: bool, matches: &[usize]) -> NestedListItem {
+        let text = format!("{0}{1}", prefix, self.title);
+        let text_len = text.chars().count();
+        let item = match is_selected {
+            true => NestedListItem::new(text).color_range(0, 0..text_len).selected(),
+            false => NestedListItem::new(text),
+        };
+        let item = highlight_mark(item, prefix, is_marked);
+        highlight_matches(item, prefix.chars().count(), matches)
     }
 }
@@ -0,0 +1,141 @@
+use zellij_tile::prelude::*;
+use std::collections::BTreeMap;
+use std::fmt;
+
+use crate::host::{Host, HostCommand};
+use crate::sessiontree::{Node, NodeKey};
+
+/// Which kind of node a request can pick, from the pipe name or a `level` arg
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum PickLevel {
+    #[default]
+    Session,
+    Tab,
+    Pane,
+}
+
+impl PickLevel {
+    /// The level of a picker request, None when the pipe is not one: only `sessionpicker`,
+    /// `tabpicker` and `panepicker` are, a `level` arg overriding their name
+    pub fn from_pipe(name: &str, args: &BTreeMap<String, String>) -> Option<Result<Self, String>> {
+        let level = name.strip_suffix("picker").filter(|level| matches!(*level, "session" | "tab" | "pane"))?;
+        let level = args.get("level").map(|level| level.as_str()).unwrap_or(level);
+        let level = match level {
+            "session" => Ok(PickLevel::Session),
+            "tab" => Ok(PickLevel::Tab),
+            "pane" => Ok(PickLevel::Pane),
+            _ => Err(format!("unknown level '{}', expected session, tab or pane", level)),
+        };
+        Some(level)
+    }
+
+    pub fn accepts(&self, key: &NodeKey) -> bool {
+        match self {
+            PickLevel::Session => matches!(key, NodeKey::Session(_) | NodeKey::DeadSession(_)),
+            PickLevel::Tab => matches!(key, NodeKey::Tab(..)),
+            PickLevel::Pane => matches!(key, NodeKey::Pane(..)),
+        }
+    }
+
+    /// The reply for a picked node, tab separated for tabs and panes:
+    /// `session` / `session<TAB>tab position<TAB>tab name` / `session<TAB>pane id<TAB>is plugin`
+    pub fn identifier(&self, node: &dyn Node) -> String {
+        match node.key() {
+            NodeKey::Tab(session, position) => format!("{}\t{}\t{}", session, position, node.name()),
+            NodeKey::Pane(session, (id, is_plugin)) => format!("{}\t{}\t{}", session, id, is_plugin),
+            _ => node.identifier(),
+        }
+    }
+}
+
+impl fmt::Display for PickLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PickLevel::Session => write!(f, "session"),
+            PickLevel::Tab => write!(f, "tab"),
+            PickLevel::Pane => write!(f, "pane"),
+        }
+    }
+}
+
+pub enum PickReply {
+    Picked(String),
+    Cancelled,
+    Error(String),
+}
+
+/// How the picked node is described in the reply, from the `format` arg
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum PickFormat {
+    #[default]
+    Text,
+    Json,
+}
+
+impl PickFormat {
+    pub fn from_args(args: &BTreeMap<String, String>) -> Self {
+        match args.get("format").map(|format| format.as_str()) {
+            Some("json") => PickFormat::Json,
+            _ => PickFormat::Text,
+        }
+    }
+}
+
+/// A pending `sessionpicker`, `tabpicker` or `panepicker` request, answered once the user picks,
+/// cancels or an error occurs
+///
+/// Plugins receive a `<name>_result` message with a `status` arg of `ok`, `cancelled` or
+/// `error`, the payload being the picked identifier or the error. The cli only receives the picked
+/// identifier, an empty output means nothing was picked.
+pub struct PickRequest {
+    name: String,
+    level: PickLevel,
+    format: PickFormat,
+    source: PipeSource,
+    args: BTreeMap<String, String>,
+}
+
+impl PickRequest {
+    pub fn new(host: &dyn Host, name: String, level: PickLevel, source: PipeSource, args: BTreeMap<String, String>) -> Self {
+        if let PipeSource::Cli(pipe_id) = &source {
+            host.run(HostCommand::BlockCliPipeInput(pipe_id.clone()));
+        }
+        let format = PickFormat::from_args(&args);
+        Self { name, level, format, source, args }
+    }
+
+    pub fn level(&self) -> PickLevel {
+        self.level
+    }
+
+    pub fn format(&self) -> PickFormat {
+        self.format
+    }
+
+    pub fn reply(self, host: &dyn Host, reply: PickReply) {
+        let (status, payload) = match reply {
+            PickReply::Picked(name) => ("ok", Some(name)),
+            PickReply::Cancelled => ("cancelled", None),
+            PickReply::Error(error) => ("error", Some(error)),
+        };
+        match self.source {
+            PipeSource::Plugin(plugin_id) => {
+                let mut args = self.args;
+                args.insert("status".to_string(), status.to_string());
+                host.run(HostCommand::MessageToPlugin {
+                    name: format!("{}_result", self.name),
+                    plugin_id,
+                    args,
+                    payload,
+                });
+            }
+            PipeSource::Cli(pipe_id) => {
+                if let ("ok", Some(name)) = (status, payload) {
+                    host.run(HostCommand::CliPipeOutput(pipe_id.clone(), name));
+                }
+                host.run(HostCommand::UnblockCliPipeInput(pipe_id));
+            }
+            PipeSource::Keybind => (),
+        }
+    }
+}
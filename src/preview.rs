@@ -0,0 +1,96 @@
+use zellij_tile::prelude::*;
+
+/// Below this width the preview would be too small to be readable
+pub const MIN_PREVIEW_COLS: usize = 60;
+
+#[derive(Clone, Copy, Default)]
+pub struct PaneGeometry {
+    pub x: usize,
+    pub y: usize,
+    pub rows: usize,
+    pub columns: usize,
+    pub is_floating: bool,
+}
+
+impl From<&PaneInfo> for PaneGeometry {
+    fn from(pane: &PaneInfo) -> Self {
+        Self {
+            x: pane.pane_x,
+            y: pane.pane_y,
+            rows: pane.pane_rows,
+            columns: pane.pane_columns,
+            is_floating: pane.is_floating,
+        }
+    }
+}
+
+pub struct PreviewPane {
+    pub title: String,
+    pub geometry: PaneGeometry,
+    pub is_selected: bool,
+    pub is_focused: bool,
+}
+
+/// Draw a scaled box diagram of the panes of a tab, floating panes on top of the tiled ones
+pub fn render_preview(panes: &[PreviewPane], x: usize, y: usize, width: usize, height: usize) {
+    let tiled = panes.iter().filter(|pane| !pane.geometry.is_floating);
+    // the tiled panes need not start at 0, e.g. below a hidden tab-bar
+    let min_x = tiled.clone().map(|pane| pane.geometry.x).min().unwrap_or(0);
+    let min_y = tiled.clone().map(|pane| pane.geometry.y).min().unwrap_or(0);
+    let total_columns = tiled.clone().map(|pane| pane.geometry.x + pane.geometry.columns - min_x).max().unwrap_or(0);
+    let total_rows = tiled.map(|pane| pane.geometry.y + pane.geometry.rows - min_y).max().unwrap_or(0);
+    if total_columns == 0 || total_rows == 0 || width < 2 || height < 2 {
+        return;
+    }
+    let mut grid = vec![vec![' '; width]; height];
+    // color level of each cell, if highlighted
+    let mut colors: Vec<Vec<Option<usize>>> = vec![vec![None; width]; height];
+    let ordered = panes.iter().filter(|pane| !pane.geometry.is_floating).chain(panes.iter().filter(|pane| pane.geometry.is_floating));
+    for pane in ordered {
+        let geometry = PaneGeometry {
+            x: pane.geometry.x.saturating_sub(min_x),
+            y: pane.geometry.y.saturating_sub(min_y),
+            ..pane.geometry
+        };
+        let left = (geometry.x * width / total_columns).min(width - 1);
+        let right = ((geometry.x + geometry.columns) * width / total_columns).saturating_sub(1).min(width - 1);
+        let top = (geometry.y * height / total_rows).min(height - 1);
+        let bottom = ((geometry.y + geometry.rows) * height / total_rows).saturating_sub(1).min(height - 1);
+        if right <= left || bottom <= top {
+            continue;
+        }
+        let color = match (pane.is_selected, pane.is_focused) {
+            (true, _) => Some(0),
+            (false, true) => Some(2),
+            (false, false) => None,
+        };
+        for (row, line) in grid.iter_mut().enumerate().take(bottom + 1).skip(top) {
+            for (column, cell) in line.iter_mut().enumerate().take(right + 1).skip(left) {
+                *cell = match (row == top, row == bottom, column == left, column == right) {
+                    (true, _, true, _) => '┌',
+                    (true, _, _, true) => '┐',
+                    (_, true, true, _) => '└',
+                    (_, true, _, true) => '┘',
+                    (true, _, _, _) | (_, true, _, _) => '─',
+                    (_, _, true, _) | (_, _, _, true) => '│',
+                    _ => ' ',
+                };
+                colors[row][column] = color;
+            }
+        }
+        let title_row = if bottom - top > 1 { top + 1 } else { top };
+        for (i, c) in pane.title.chars().take(right - left - 1).enumerate() {
+            grid[title_row][left + 1 + i] = c;
+        }
+    }
+    for (row, line) in grid.iter().enumerate() {
+        let mut text = Text::new(line.iter().collect::<String>());
+        for level in [0, 2] {
+            let indices: Vec<usize> = (0..width).filter(|column| colors[row][*column] == Some(level)).collect();
+            if !indices.is_empty() {
+                text = text.color_indices(level, indices);
+            }
+        }
+        print_text_with_coordinates(text, x, y + row, Some(width), Some(1));
+    }
+}
@@ -1,96 +1,82 @@
 use zellij_tile::prelude::*;
-use std::rc::Rc;
-use std::cell::RefCell;
+use serde_json::{json, Value};
 
-use crate::sessiontree::Node;
+use crate::host::{Host, HostCommand};
+use crate::preview::PaneGeometry;
+use crate::sessiontree::{Node, NodeKey};
+use crate::utils::{highlight_mark, highlight_matches};
 
 pub struct Session {
-    index: usize,
     name: String,
     is_current_session: bool,
-    tabs: Vec<Rc<RefCell<dyn Node>>>,
-    is_expanded: bool,
 }
 
 impl Session {
-    pub fn new(index: usize, name: String, is_current_session: bool) -> Self {
+    pub fn new(name: String, is_current_session: bool) -> Self {
         Self {
-            index,
             name,
             is_current_session,
-            tabs: Vec::new(),
-            is_expanded: false,
         }
     }
 }
 
 impl Node for Session {
-    fn index(&self) -> usize {
-        self.index
-    }
     fn identifier(&self) -> String {
         self.name.clone()
     }
+    fn key(&self) -> NodeKey {
+        NodeKey::Session(self.name.clone())
+    }
+    fn name(&self) -> String {
+        self.name.clone()
+    }
     fn is_focused(&self) -> bool {
         self.is_current_session
     }
-    fn focus(&self) -> Result<(), String> {
+    fn focus(&self, host: &dyn Host) -> Result<(), String> {
         if self.is_current_session {
-            hide_self();
+            host.run(HostCommand::HideSelf);
             return Err("cannot goto current session".to_string());
         }
-        switch_session(Some(&self.name));
-        hide_self();
+        host.run(HostCommand::SwitchSession(self.name.clone()));
+        host.run(HostCommand::HideSelf);
         Ok(())
     }
-    fn kill(&self) -> Result<(), String> {
-        kill_sessions(&[self.name.clone()]);
+    fn kill(&self, host: &dyn Host) -> Result<(), String> {
+        host.run(HostCommand::KillSessions(vec![self.name.clone()]));
         Ok(())
     }
-    fn parent(&self) -> Option<Rc<RefCell<dyn Node>>> {
-        None
-    }
-    fn add_child(&mut self, child: Rc<RefCell<dyn Node>>) {
-        self.tabs.push(child);
-    }
-    fn is_shown(&self) -> bool {
-        true
-    }
-    fn show(&mut self) {
-    }
-    fn hide(&mut self) {
+    fn can_rename(&self) -> Result<(), String> {
+        match self.is_current_session {
+            true => Ok(()),
+            false => Err("can only rename the current session".to_string()),
+        }
     }
-    fn is_expanded(&self) -> bool {
-        self.is_expanded
+    fn rename(&self, host: &dyn Host, name: &str) -> Result<(), String> {
+        self.can_rename()?;
+        host.run(HostCommand::RenameSession(name.to_string()));
+        Ok(())
     }
-    fn expand(&mut self) {
-        if self.is_expanded {
-            return;
-        }
-        self.is_expanded = true;
-        for tab in self.tabs.iter() {
-            tab.borrow_mut().show();
-        }
+    fn geometry(&self) -> Option<PaneGeometry> {
+        None
     }
-    fn collapse(&mut self) {
-        if !self.is_expanded {
-            return;
-        }
-        self.is_expanded = false;
-        for tab in self.tabs.iter() {
-            tab.borrow_mut().collapse();
-            tab.borrow_mut().hide();
-        }
+    fn to_json(&self) -> Value {
+        json!({
+            "session": self.name,
+            "is_current": self.is_current_session,
+        })
     }
-    fn render(&self, keybind: String, is_selected: bool) -> NestedListItem {
+    fn render(&self, prefix: &str, is_selected: bool, is_marked: bool, matches: &[usize]) -> NestedListItem {
         let text = match self.is_current_session {
-            true => format!("({0}) {1} (attached)", keybind, self.name),
-            false => format!("({0}) {1}", keybind, self.name),
+            true => format!("{0}{1} (attached)", prefix, self.name),
+            false => format!("{0}{1}", prefix, self.name),
         };
-        let text_len = text.len();
-        match is_selected {
-            true => NestedListItem::new(text).indent(0).color_range(0, 0..text_len).selected(),
-            false => NestedListItem::new(text).indent(0),
-        }
+        let text_len = text.chars().count();
+        let item = match is_selected {
+            true => NestedListItem::new(text).color_range(0, 0..text_len).selected(),
+            false => NestedListItem::new(text),
+        };
+        let item = highlight_mark(item, prefix, is_marked);
+        highlight_matches(item, prefix.chars().count(), matches)
     }
 }
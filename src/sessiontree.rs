@@ -1,138 +1,423 @@
 use zellij_tile::prelude::*;
-use crate::utils::{IdGenerator, KeybindGenerator};
+use crate::utils::{fuzzy_match, pluralize, render_prefix};
 use crate::session::Session;
 use crate::tab::Tab;
 use crate::pane::Pane;
-use crate::config::Config;
+use crate::exitedgroup::ExitedGroup;
+use crate::deadsession::DeadSession;
+use crate::group::Group;
+use crate::preview::{render_preview, PaneGeometry, PreviewPane, MIN_PREVIEW_COLS};
+use crate::config::{Config, HintAlphabet};
+use crate::host::{Host, HostCommand};
 
-use std::rc::Rc;
-use std::cell::RefCell;
+use std::cmp::Reverse;
+use std::collections::HashSet;
+use std::fmt;
+use std::iter;
+use std::time::Duration;
+use serde_json::{json, Map, Value};
 
 #[derive(Default)]
 pub struct SessionTree {
-    nodes: Vec<Rc<RefCell<dyn Node>>>,
-    cursor: i32,
-    quick_find: Vec<usize>,
+    // every node in pre-order: sessions followed by their tabs, each followed by its panes
+    nodes: Vec<TreeNode>,
+    cursor: usize,
+    // node labelled by each quick-select key in the last render, None once it no longer exists
+    quick_find: Vec<Option<NodeId>>,
+    hint_alphabet: HintAlphabet,
+    // start of a quick-select hint spanning several keys
+    hint: String,
+    query: String,
+    // matched character indices of each node, only populated while filtering
+    matches: Vec<Option<Vec<usize>>>,
+    filtered: Vec<bool>,
+    // shown under the selected node, eg. to confirm a kill
+    prompt: Option<String>,
+    // new name being typed for the selected node
+    editing: Option<String>,
+    marked: HashSet<NodeKey>,
+    // first line shown when scrolled with the mouse, the selected node is centered otherwise
+    scroll: Option<usize>,
+    // node of each line of the last render and where it was drawn, to map clicks back to nodes
+    rendered: Vec<Option<NodeId>>,
+    rendered_y: usize,
+    rendered_from: usize,
+    // height of the last render, None until the tree is shown
+    rendered_rows: Option<usize>,
+}
+
+/// Position of a node in the tree, only valid until the tree is rebuilt, see `NodeKey` otherwise
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct NodeId(usize);
+
+/// A node of the tree, linked to its parent and children by id
+struct TreeNode {
+    kind: NodeKind,
+    parent: Option<NodeId>,
+    children: Vec<NodeId>,
+    is_expanded: bool,
+}
+
+pub enum NodeKind {
+    Group(Group),
+    Session(Session),
+    Tab(Tab),
+    Pane(Pane),
+    Exited(ExitedGroup),
+    DeadSession(DeadSession),
+}
+
+impl NodeKind {
+    pub fn node(&self) -> &dyn Node {
+        match self {
+            NodeKind::Group(group) => group,
+            NodeKind::Session(session) => session,
+            NodeKind::Tab(tab) => tab,
+            NodeKind::Pane(pane) => pane,
+            NodeKind::Exited(exited) => exited,
+            NodeKind::DeadSession(dead) => dead,
+        }
+    }
+}
+
+/// Identifies a node across rebuilds of the tree
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum NodeKey {
+    Session(String),
+    Tab(String, usize),
+    Pane(String, (u32, bool)),
+    Exited,
+    DeadSession(String),
+    Group(String),
+}
+
+impl fmt::Display for NodeKey {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            NodeKey::Session(name) => write!(f, "session '{}'", name),
+            NodeKey::Tab(session, position) => write!(f, "tab {} of '{}'", position, session),
+            NodeKey::Pane(session, (id, _)) => write!(f, "pane {} of '{}'", id, session),
+            NodeKey::Exited => write!(f, "exited sessions"),
+            NodeKey::DeadSession(name) => write!(f, "exited session '{}'", name),
+            NodeKey::Group(name) => write!(f, "group '{}'", name),
+        }
+    }
 }
 
 pub trait Node {
-    fn index(&self) -> usize;
     fn identifier(&self) -> String;
+    fn key(&self) -> NodeKey;
+    fn name(&self) -> String;
     fn is_focused(&self) -> bool;
-    fn focus(&self) -> Result<(), String>;
-    fn kill(&self) -> Result<(), String>;
-    fn parent(&self) -> Option<Rc<RefCell<dyn Node>>>;
-    fn add_child(&mut self, child: Rc<RefCell<dyn Node>>);
-    fn is_shown(&self) -> bool;
-    fn show(&mut self);
-    fn hide(&mut self);
-    fn is_expanded(&self) -> bool;
-    fn expand(&mut self);
-    fn collapse(&mut self);
-    fn render(&self, keybind: String, is_selected: bool) -> NestedListItem;
+    fn focus(&self, host: &dyn Host) -> Result<(), String>;
+    fn kill(&self, host: &dyn Host) -> Result<(), String>;
+    /// Whether `rename` would succeed, checked before the new name is typed
+    fn can_rename(&self) -> Result<(), String>;
+    fn rename(&self, host: &dyn Host, name: &str) -> Result<(), String>;
+    fn geometry(&self) -> Option<PaneGeometry>;
+    fn to_json(&self) -> Value;
+    fn render(&self, prefix: &str, is_selected: bool, is_marked: bool, matches: &[usize]) -> NestedListItem;
 }
 
-
+/// Append a node to the tree, as the last child of its parent
+fn push_node(nodes: &mut Vec<TreeNode>, kind: NodeKind, parent: Option<NodeId>) -> NodeId {
+    let id = NodeId(nodes.len());
+    nodes.push(TreeNode {
+        kind,
+        parent,
+        children: Vec::new(),
+        is_expanded: false,
+    });
+    if let Some(parent) = parent {
+        nodes[parent.0].children.push(id);
+    }
+    id
+}
 
 impl SessionTree {
-    pub fn new(sessions: Vec<SessionInfo>, config: &Config) -> Self {
-        let mut nodes: Vec<Rc<RefCell<dyn Node>>> = Vec::new();
-        let mut id_generator = IdGenerator::new();
+    pub fn new(sessions: Vec<SessionInfo>, resurrectable: Vec<(String, Duration)>, config: &Config) -> Self {
+        let mut nodes = Vec::new();
+        // sessions of a group are listed together, where the first of them is
+        let mut entries: Vec<(Option<String>, Vec<&SessionInfo>)> = Vec::new();
         for session in sessions.iter() {
-            let session_index = id_generator.next();
-            let session_node: Rc<RefCell<dyn Node>> = Rc::new(RefCell::new(Session::new(session_index, session.name.clone(), session.is_current_session)));
-            nodes.push(session_node.clone());
-            for tab in session.tabs.iter() {
-                let tab_index = id_generator.next();
-                let tab_node = Rc::new(RefCell::new(Tab::new(tab_index, tab.name.clone(), tab.position, session_node.clone(), tab.active)));
-                session_node.borrow_mut().add_child(tab_node.clone());
-                nodes.push(tab_node.clone());
-                for pane in session.panes.panes.get(&tab.position).unwrap_or(&Vec::new()) {
-                    if !config.show_plugins && pane.is_plugin {
-                        continue;
-                    }
-                    let pane_index = id_generator.next();
-                    let pane_node = Rc::new(RefCell::new(Pane::new(pane_index, pane.title.clone(), (pane.id, pane.is_plugin), tab_node.clone(), pane.is_focused)));
-                    tab_node.borrow_mut().add_child(pane_node.clone());
-                    nodes.push(pane_node.clone());
+            let group = config.group_by.as_ref().and_then(|group_by| group_by.group(&session.name));
+            match entries.iter_mut().find(|(name, _)| group.is_some() && *name == group) {
+                Some((_, members)) => members.push(session),
+                None => entries.push((group, vec![session])),
+            }
+        }
+        for (group, members) in entries {
+            let group_id = group.map(|name| push_node(&mut nodes, NodeKind::Group(Group::new(name)), None));
+            for session in members {
+                let start = nodes.len();
+                Self::push_session(&mut nodes, session, group_id, config);
+                let tabs = session.tabs.len();
+                let panes = nodes.len() - start - 1 - tabs;
+                if let Some(NodeKind::Group(group)) = group_id.map(|group_id| &mut nodes[group_id.0].kind) {
+                    group.add_session(session.name.clone(), session.is_current_session, tabs, panes);
                 }
             }
         }
+        if !resurrectable.is_empty() {
+            let exited = push_node(&mut nodes, NodeKind::Exited(ExitedGroup::new(resurrectable.len())), None);
+            for (name, age) in resurrectable.into_iter() {
+                push_node(&mut nodes, NodeKind::DeadSession(DeadSession::new(name, age)), Some(exited));
+            }
+        }
         Self {
             nodes,
             cursor: 0,
             quick_find: Vec::new(),
+            hint_alphabet: config.hint_alphabet.clone(),
+            hint: String::new(),
+            query: String::new(),
+            matches: Vec::new(),
+            filtered: Vec::new(),
+            prompt: None,
+            editing: None,
+            marked: HashSet::new(),
+            scroll: None,
+            rendered: Vec::new(),
+            rendered_y: 0,
+            rendered_from: 0,
+            rendered_rows: None,
         }
     }
 
-    pub fn get_current_node(&self) -> Result<Rc<RefCell<dyn Node>>, String> {
-        let node = self.nodes.get(self.cursor as usize).ok_or("cursor out of range")?;
-        Ok(node.clone())
+    /// Push a session followed by its tabs and their panes
+    fn push_session(nodes: &mut Vec<TreeNode>, session: &SessionInfo, group: Option<NodeId>, config: &Config) {
+        let session_id = push_node(nodes, NodeKind::Session(Session::new(session.name.clone(), session.is_current_session)), group);
+        for tab in session.tabs.iter() {
+            let tab_node = Tab::new(tab.name.clone(), tab.position, tab.active, nodes[session_id.0].kind.node());
+            let panes: Vec<Pane> = session.panes.panes.get(&tab.position).into_iter().flatten()
+                .filter(|pane| config.show_plugins || !pane.is_plugin)
+                .map(|pane| Pane::new(pane.title.clone(), (pane.id, pane.is_plugin), pane.into(), pane.terminal_command.clone().or(pane.plugin_url.clone()), &tab_node, pane.is_focused))
+                .collect();
+            let tab_id = push_node(nodes, NodeKind::Tab(tab_node), Some(session_id));
+            for pane in panes {
+                push_node(nodes, NodeKind::Pane(pane), Some(tab_id));
+            }
+        }
     }
 
-    pub fn get_parent(&self, index: usize) -> Result<Rc<RefCell<dyn Node>>, String> {
-        let session = match self.get_node(index)?.borrow().parent() {
-            Some(parent) => parent,
-            None => self.get_node(index)?,
+    /// Rebuild the tree from a new session update, keeping the cursor on the same
+    /// logical node and preserving which sessions and tabs are expanded
+    pub fn update(&mut self, sessions: Vec<SessionInfo>, resurrectable: Vec<(String, Duration)>, config: &Config) {
+        let expanded: HashSet<NodeKey> = self.nodes.iter()
+            .filter(|node| node.is_expanded)
+            .map(|node| node.kind.node().key())
+            .collect();
+        let selected: Vec<NodeKey> = match self.current() {
+            Ok(id) => self.lineage(id).map(|id| self.node(id).key()).collect(),
+            Err(_) => Vec::new(),
         };
-        Ok(session)
-    }
+        let previous_cursor = self.cursor;
+        let query = std::mem::take(&mut self.query);
+        let hint = std::mem::take(&mut self.hint);
+        let prompt = self.prompt.take();
+        let editing = self.editing.take();
+        let marked = std::mem::take(&mut self.marked);
+        let scroll = self.scroll;
+        let rendered_rows = self.rendered_rows;
+        let quick_find: Vec<Option<NodeKey>> = self.quick_find.iter()
+            .map(|id| id.map(|id| self.node(id).key()))
+            .collect();
 
-    pub fn get_session(&self, index: usize) -> Result<Rc<RefCell<dyn Node>>, String> {
-        let mut current = self.get_node(index)?.clone();
-        while current.borrow().parent().is_some() {
-            current = self.get_parent(current.clone().borrow().index())?;
+        *self = SessionTree::new(sessions, resurrectable, config);
+        for node in self.nodes.iter_mut() {
+            if expanded.contains(&node.kind.node().key()) {
+                node.is_expanded = true;
+            }
+        }
+        self.apply_query(query);
+        self.hint = hint;
+        self.prompt = prompt;
+        self.editing = editing;
+        self.marked = marked.into_iter().filter(|key| self.find(key).is_some()).collect();
+        self.scroll = scroll;
+        self.rendered_rows = rendered_rows;
+        // the keys on screen keep acting on their nodes until the next render
+        self.quick_find = quick_find.iter()
+            .map(|key| key.as_ref().and_then(|key| self.find(key)))
+            .collect();
+        // The selected node or its closest surviving ancestor
+        let cursor = selected.iter().find_map(|key| {
+            self.ids().find(|&id| self.node(id).key() == *key && self.is_visible(id))
+        });
+        match cursor {
+            Some(cursor) => self.cursor = cursor.0,
+            None => {
+                self.cursor = previous_cursor.min(self.nodes.len().saturating_sub(1));
+                while self.cursor > 0 && !self.is_visible(NodeId(self.cursor)) {
+                    self.cursor -= 1;
+                }
+            }
         }
-        Ok(current)
     }
 
-    pub fn get_node(&self, index: usize) -> Result<Rc<RefCell<dyn Node>>, String> {
-        let session = self.nodes.get(index).ok_or("session index out of range")?;
-        Ok(session.clone())
+    fn ids(&self) -> impl Iterator<Item = NodeId> {
+        (0..self.nodes.len()).map(NodeId)
     }
 
-    pub fn expand(&mut self, index: usize) -> Result<(), String> {
-        let node = self.get_node(index)?;
-        node.borrow_mut().expand();
-        Ok(())
+    /// The node followed by its ancestors up to the root
+    fn lineage(&self, id: NodeId) -> impl Iterator<Item = NodeId> + '_ {
+        iter::successors(Some(id), |id| self.nodes[id.0].parent)
     }
-    pub fn collapse(&mut self, index: usize) -> Result<(), String> {
-        let parent = self.get_parent(index)?;
-        let next_position = parent.borrow().index() as i32;
-        parent.borrow_mut().collapse();
-        self.cursor = next_position;
-        Ok(())
+
+    fn descendants(&self, id: NodeId) -> Vec<NodeId> {
+        self.nodes[id.0].children.iter()
+            .flat_map(|&child| iter::once(child).chain(self.descendants(child)))
+            .collect()
     }
 
-    fn wraping_previous(&mut self) {
-        if self.cursor == 0 {
-            self.cursor = (self.nodes.len() - 1) as i32;
+    pub fn node(&self, id: NodeId) -> &dyn Node {
+        self.nodes[id.0].kind.node()
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    /// Filter the tree down to the nodes fuzzy matching the query and their ancestors,
+    /// moving the cursor to the first match if the selected node no longer matches
+    pub fn set_query(&mut self, query: String) {
+        self.apply_query(query);
+        let cursor = self.cursor;
+        if self.matches.get(cursor).is_some_and(|m| m.is_some()) || (self.query.is_empty() && self.is_visible(NodeId(cursor))) {
+            return;
+        }
+        if let Some(first) = self.matches.iter().position(|m| m.is_some()) {
+            self.cursor = first;
         } else {
-            self.cursor -= 1;
+            while self.cursor > 0 && !self.is_visible(NodeId(self.cursor)) {
+                self.cursor -= 1;
+            }
         }
     }
 
-    fn wraping_next(&mut self) {
-        if self.cursor as usize == self.nodes.len() - 1 {
-            self.cursor = 0;
-        } else {
-            self.cursor += 1;
+    fn apply_query(&mut self, query: String) {
+        self.query = query;
+        self.matches = Vec::new();
+        self.filtered = Vec::new();
+        if self.query.is_empty() {
+            return;
+        }
+        self.matches = self.nodes.iter().map(|node| fuzzy_match(&self.query, &node.kind.node().name())).collect();
+        let mut filtered = vec![false; self.nodes.len()];
+        for id in self.ids().filter(|id| self.matches[id.0].is_some()) {
+            for id in self.lineage(id) {
+                filtered[id.0] = true;
+            }
+        }
+        self.filtered = filtered;
+    }
+
+    /// Whether the node is part of the rendered tree, either through folding or filtering
+    pub fn is_visible(&self, id: NodeId) -> bool {
+        if id.0 >= self.nodes.len() {
+            return false;
+        }
+        if self.query.is_empty() {
+            return self.lineage(id).skip(1).all(|ancestor| self.nodes[ancestor.0].is_expanded);
+        }
+        self.filtered.get(id.0).copied().unwrap_or(false)
+    }
+
+    /// The selected node, none being selected when a filter matches nothing
+    pub fn current(&self) -> Result<NodeId, String> {
+        if self.cursor >= self.nodes.len() {
+            return Err("cursor out of range".to_string());
+        }
+        match self.is_visible(NodeId(self.cursor)) {
+            true => Ok(NodeId(self.cursor)),
+            false => Err("no node selected".to_string()),
+        }
+    }
+
+    /// The parent of the node, or the node itself at the root
+    fn get_parent(&self, id: NodeId) -> NodeId {
+        self.nodes[id.0].parent.unwrap_or(id)
+    }
+
+    /// JSON description of a node merged with the ones of its ancestors, along with the names
+    /// leading to it from the root in `path`
+    pub fn to_json(&self, id: NodeId) -> Value {
+        let mut object = Map::new();
+        let mut path = Vec::new();
+        for id in self.lineage(id) {
+            let node = self.node(id);
+            if let Value::Object(fields) = node.to_json() {
+                for (field, value) in fields {
+                    object.entry(field).or_insert(value);
+                }
+            }
+            path.push(node.name());
         }
+        path.reverse();
+        object.insert("path".to_string(), json!(path));
+        Value::Object(object)
+    }
+
+    /// The node itself or its closest ancestor matching the predicate
+    pub fn find_ancestor(&self, id: NodeId, predicate: impl Fn(&NodeKey) -> bool) -> Option<NodeId> {
+        self.lineage(id).find(|&id| predicate(&self.node(id).key()))
+    }
+
+    /// Nodes without children are considered expanded, there is nothing left to show
+    fn is_expanded(&self, id: NodeId) -> bool {
+        let node = &self.nodes[id.0];
+        node.children.is_empty() || node.is_expanded
+    }
+
+    /// Whether the node is expanded, None for nodes without children
+    fn fold(&self, id: NodeId) -> Option<bool> {
+        let node = &self.nodes[id.0];
+        (!node.children.is_empty()).then_some(node.is_expanded)
+    }
+
+    fn expand(&mut self, id: NodeId) {
+        self.nodes[id.0].is_expanded = true;
+    }
+
+    /// Collapse the node along with its descendants
+    fn collapse_node(&mut self, id: NodeId) {
+        self.nodes[id.0].is_expanded = false;
+        for child in self.nodes[id.0].children.clone() {
+            self.collapse_node(child);
+        }
+    }
+
+    /// Collapse the parent of the node, moving the cursor to it
+    fn collapse(&mut self, id: NodeId) {
+        let parent = self.get_parent(id);
+        self.collapse_node(parent);
+        self.cursor = parent.0;
+    }
+
+    fn wraping_previous(&mut self) {
+        self.cursor = match self.cursor {
+            0 => self.nodes.len().saturating_sub(1),
+            cursor => cursor - 1,
+        };
+    }
+
+    fn wraping_next(&mut self) {
+        self.cursor = (self.cursor + 1) % self.nodes.len().max(1);
     }
 
     fn saturating_previous(&mut self) {
-        self.cursor = 0.max(self.cursor - 1);
+        self.cursor = self.cursor.saturating_sub(1);
     }
 
     fn saturating_next(&mut self) {
-        self.cursor = (self.nodes.len() as i32 - 1).min(self.cursor + 1)
+        self.cursor = (self.cursor + 1).min(self.nodes.len().saturating_sub(1));
     }
 
 
     pub fn handle_down(&mut self) -> Result<(), String> {
         for _ in 0..=self.nodes.len() {
             self.wraping_next();
-            if self.get_current_node()?.borrow().is_shown() {
+            if self.is_visible(NodeId(self.cursor)) {
                 break;
             }
         }
@@ -142,7 +427,7 @@ impl SessionTree {
     pub fn handle_up(&mut self) -> Result<(), String> {
         for _ in 0..=self.nodes.len() {
             self.wraping_previous();
-            if self.get_current_node()?.borrow().is_shown() {
+            if self.is_visible(NodeId(self.cursor)) {
                 break;
             }
         }
@@ -150,13 +435,14 @@ impl SessionTree {
     }
 
     pub fn handle_left(&mut self) -> Result<(), String> {
-        let parent = self.get_parent(self.cursor as usize)?;
-        if parent.borrow().is_expanded() {
-            self.collapse(self.cursor as usize)?;
+        let current = self.current()?;
+        let parent = self.get_parent(current);
+        if self.is_expanded(parent) {
+            self.collapse(current);
         } else {
             for _ in 0..=self.nodes.len() {
                 self.saturating_previous();
-                if self.get_current_node()?.borrow().is_shown() {
+                if self.is_visible(NodeId(self.cursor)) {
                     break;
                 }
             }
@@ -165,13 +451,13 @@ impl SessionTree {
     }
 
     pub fn handle_right(&mut self) -> Result<(), String> {
-        let current = self.get_current_node()?;
-        if !current.borrow().is_expanded() {
-            self.expand(self.cursor as usize)?;
+        let current = self.current()?;
+        if !self.is_expanded(current) {
+            self.expand(current);
         } else {
             for _ in 0..=self.nodes.len() {
                 self.saturating_next();
-                if self.get_current_node()?.borrow().is_shown() {
+                if self.is_visible(NodeId(self.cursor)) {
                     break;
                 }
             }
@@ -179,34 +465,712 @@ impl SessionTree {
         Ok(())
     }
 
-    pub fn switch_by_index(&mut self, target: usize) -> Result<(), String> {
-        let node_id = self.quick_find.get(target).ok_or("quick_find index out of range")?;
-        let node = self.get_node(*node_id)?;
-        node.borrow().focus()?;
-        Ok(())
+    fn quick_find_node(&self, target: usize) -> Result<NodeId, String> {
+        let id = self.quick_find.get(target).ok_or("no node has this quick-select key")?
+            .ok_or("node no longer exists")?;
+        Ok(id)
     }
 
-    pub fn switch_to_selected(&self) -> Result<(), String> {
-        let node = self.get_current_node()?;
-        node.borrow().focus()?;
+    pub fn switch_by_index(&mut self, host: &dyn Host, target: usize) -> Result<String, String> {
+        self.focus(host, self.quick_find_node(target)?)
+    }
+
+    /// Select the node labelled with a quick-select key, eg. to pick it
+    pub fn select_by_index(&mut self, target: usize) -> Result<(), String> {
+        let id = self.quick_find_node(target)?;
+        self.select(id);
         Ok(())
     }
 
-    pub fn kill_selected(& self) -> Result<(), String> {
-        let node = self.get_current_node()?;
-        node.borrow().kill()?;
+    /// Type the next key of a quick-select hint, returning the index of the hint once complete
+    pub fn type_hint(&mut self, c: char) -> Result<Option<usize>, String> {
+        self.hint.push(c);
+        let hints = self.hint_alphabet.hints(self.quick_find.len());
+        if let Some(index) = hints.iter().position(|hint| *hint == self.hint) {
+            self.hint.clear();
+            return Ok(Some(index));
+        }
+        match hints.iter().any(|hint| hint.starts_with(&self.hint)) {
+            true => Ok(None),
+            false => Err(format!("no node has the quick-select key {}", std::mem::take(&mut self.hint))),
+        }
+    }
+
+    /// Forget the start of a hint, returning whether one was being typed
+    pub fn clear_hint(&mut self) -> bool {
+        let was_typing = !self.hint.is_empty();
+        self.hint.clear();
+        was_typing
+    }
+
+    pub fn current_session(&self) -> Option<String> {
+        self.nodes.iter()
+            .find_map(|node| match &node.kind {
+                NodeKind::Session(session) if session.is_focused() => Some(session.name()),
+                _ => None,
+            })
+    }
+
+    /// Focus a node, returning the name of its session
+    fn focus(&self, host: &dyn Host, id: NodeId) -> Result<String, String> {
+        self.node(id).focus(host)?;
+        let session = self.lineage(id)
+            .find(|id| matches!(self.nodes[id.0].kind, NodeKind::Session(_) | NodeKind::DeadSession(_)))
+            .ok_or("node has no session")?;
+        Ok(self.node(session).name())
+    }
+
+    /// Switch to the session `offset` places after the current one, looping around
+    pub fn cycle_session(&self, host: &dyn Host, offset: isize) -> Result<String, String> {
+        let sessions: Vec<NodeId> = self.ids()
+            .filter(|id| matches!(self.nodes[id.0].kind, NodeKind::Session(_)))
+            .collect();
+        let current = sessions.iter()
+            .position(|&id| self.node(id).is_focused())
+            .ok_or("no current session")?;
+        let target = (current as isize + offset).rem_euclid(sessions.len() as isize) as usize;
+        self.focus(host, sessions[target])
+    }
+
+    pub fn focus_node(&self, host: &dyn Host, key: &NodeKey) -> Result<String, String> {
+        let id = self.find(key).ok_or(format!("{} does not exist", key))?;
+        self.focus(host, id)
+    }
+
+    pub fn expand_all(&mut self) {
+        for node in self.nodes.iter_mut() {
+            node.is_expanded = true;
+        }
+    }
+
+    pub fn switch_to_selected(&self, host: &dyn Host) -> Result<String, String> {
+        self.focus(host, self.current()?)
+    }
+
+    /// Mark or unmark the selected node, then move down
+    pub fn toggle_mark(&mut self) -> Result<(), String> {
+        let key = self.node(self.current()?).key();
+        if !self.marked.remove(&key) {
+            self.marked.insert(key);
+        }
+        self.handle_down()
+    }
+
+    pub fn has_marks(&self) -> bool {
+        !self.marked.is_empty()
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+    }
+
+    /// The marked nodes in tree order, or the selected node if none are marked
+    pub fn targets(&self) -> Result<Vec<NodeKey>, String> {
+        if self.marked.is_empty() {
+            return Ok(vec![self.node(self.current()?).key()]);
+        }
+        Ok(self.ids()
+            .map(|id| self.node(id).key())
+            .filter(|key| self.marked.contains(key))
+            .collect())
+    }
+
+    pub fn find(&self, key: &NodeKey) -> Option<NodeId> {
+        self.ids().find(|&id| self.node(id).key() == *key)
+    }
+
+    pub fn kill_node(&self, host: &dyn Host, key: &NodeKey) -> Result<(), String> {
+        let id = self.find(key).ok_or("node no longer exists")?;
+        self.node(id).kill(host)
+    }
+
+    /// Kill all the sessions at once, then the other nodes that are not part of them
+    pub fn kill_nodes(&self, host: &dyn Host, keys: &[NodeKey]) -> Result<(), String> {
+        // groups are killed through their sessions
+        let sessions: Vec<String> = keys.iter().flat_map(|key| match key {
+            NodeKey::Session(name) => vec![name.clone()],
+            NodeKey::Group(_) => self.find(key)
+                .map(|group| self.nodes[group.0].children.iter().map(|&session| self.node(session).name()).collect())
+                .unwrap_or_default(),
+            _ => Vec::new(),
+        }).collect();
+        if !sessions.is_empty() {
+            host.run(HostCommand::KillSessions(sessions.clone()));
+        }
+        let mut others: Vec<&NodeKey> = keys.iter()
+            .filter(|key| match key {
+                NodeKey::Session(_) | NodeKey::Group(_) => false,
+                NodeKey::Tab(session, _) => !sessions.contains(session),
+                // panes go along with their tab
+                NodeKey::Pane(session, _) => !sessions.contains(session) && !self.find(key)
+                    .and_then(|pane| self.nodes[pane.0].parent)
+                    .is_some_and(|tab| keys.contains(&self.node(tab).key())),
+                _ => true,
+            })
+            .collect();
+        // closing a tab shifts the position of the following ones, so the last tabs go first
+        others.sort_by_key(|key| match key {
+            NodeKey::Tab(_, position) => (0, Reverse(*position)),
+            _ => (1, Reverse(0)),
+        });
+        let errors: Vec<String> = others.into_iter()
+            .filter_map(|key| self.kill_node(host, key).err())
+            .collect();
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors.join(", ")),
+        }
+    }
+
+    /// Describe what killing the selected node would destroy, eg. "Kill session 'foo' with 4 tabs / 11 panes?"
+    pub fn describe_kill(&self) -> Result<String, String> {
+        if !self.marked.is_empty() {
+            let count = |predicate: fn(&NodeKey) -> bool| self.marked.iter().filter(|key| predicate(key)).count();
+            let counts = [
+                (count(|key| matches!(key, NodeKey::Session(_))), "session"),
+                (count(|key| matches!(key, NodeKey::Tab(..))), "tab"),
+                (count(|key| matches!(key, NodeKey::Pane(..))), "pane"),
+                (count(|key| matches!(key, NodeKey::Exited | NodeKey::DeadSession(_))), "exited session"),
+                (count(|key| matches!(key, NodeKey::Group(_))), "group"),
+            ];
+            let parts: Vec<String> = counts.iter()
+                .filter(|(count, _)| *count > 0)
+                .map(|(count, unit)| pluralize(*count, unit))
+                .collect();
+            return Ok(format!("Kill {} marked: {}?", pluralize(self.marked.len(), "node"), parts.join(", ")));
+        }
+        let id = self.current()?;
+        let descendants = self.descendants(id);
+        let count = |predicate: fn(&NodeKind) -> bool| descendants.iter().filter(|id| predicate(&self.nodes[id.0].kind)).count();
+        let sessions = count(|kind| matches!(kind, NodeKind::Session(_)));
+        let tabs = count(|kind| matches!(kind, NodeKind::Tab(_)));
+        let panes = count(|kind| matches!(kind, NodeKind::Pane(_)));
+        let node = self.node(id);
+        let description = match node.key() {
+            NodeKey::Session(name) => format!("Kill session '{}' with {} / {}?", name, pluralize(tabs, "tab"), pluralize(panes, "pane")),
+            NodeKey::Tab(..) => format!("Close tab '{}' with {}?", node.name(), pluralize(panes, "pane")),
+            NodeKey::Pane(..) => format!("Close pane '{}'?", node.name()),
+            NodeKey::Exited => format!("Delete {}?", pluralize(descendants.len(), "exited session")),
+            NodeKey::DeadSession(name) => format!("Delete exited session '{}'?", name),
+            NodeKey::Group(name) => {
+                format!("Kill group '{}' with {} / {} / {}?", name, pluralize(sessions, "session"), pluralize(tabs, "tab"), pluralize(panes, "pane"))
+            }
+        };
+        Ok(description)
+    }
+
+    /// Turn the selected row into a text field pre-filled with its name
+    pub fn start_rename(&mut self) -> Result<(), String> {
+        let node = self.node(self.current()?);
+        node.can_rename()?;
+        self.editing = Some(node.name());
         Ok(())
     }
 
-    pub fn render(&mut self, rows: usize, _cols: usize) {
-        let mut keybind_generator = KeybindGenerator::new();
-        let mut lines = Vec::new();
-        for (i, node) in self.nodes.iter().enumerate().filter(|(_, node)| node.borrow().is_shown()) {
-            let text = node.borrow().render(keybind_generator.next(), i == self.cursor as usize);
-            lines.push(text);
-            self.quick_find.push(i);
+    pub fn editing_mut(&mut self) -> Option<&mut String> {
+        self.editing.as_mut()
+    }
+
+    pub fn cancel_rename(&mut self) {
+        self.editing = None;
+    }
+
+    pub fn finish_rename(&mut self, host: &dyn Host) -> Result<(), String> {
+        let name = self.editing.take().ok_or("not renaming")?;
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("name cannot be empty".to_string());
+        }
+        self.node(self.current()?).rename(host, name)
+    }
+
+    fn depth(&self, id: NodeId) -> usize {
+        self.lineage(id).count() - 1
+    }
+
+    pub fn set_prompt(&mut self, prompt: Option<String>) {
+        self.prompt = prompt;
+    }
+
+    /// The quick-select key of each node when the given nodes are labelled, None for the others
+    fn quick_keys(&self, labelled: &[Option<NodeId>]) -> Vec<Option<String>> {
+        let mut quick_keys = vec![None; self.nodes.len()];
+        let hints = self.hint_alphabet.hints(labelled.len());
+        for (id, hint) in labelled.iter().zip(hints) {
+            if let Some(id) = id {
+                quick_keys[id.0] = Some(hint);
+            }
+        }
+        quick_keys
+    }
+
+    /// The quick-select keys the next render would show, every visible node being labelled
+    /// while the tree was never shown
+    fn dump_quick_keys(&self) -> Vec<Option<String>> {
+        let (lines, _) = self.window(self.rendered_rows.unwrap_or(usize::MAX));
+        let labelled: Vec<Option<NodeId>> = lines.into_iter().flatten().map(Some).collect();
+        self.quick_keys(&labelled)
+    }
+
+    /// The whole tree as nested JSON objects, each node having its `name`, `quick_key` and
+    /// `children` besides the fields of a picker reply
+    pub fn dump_json(&self) -> Value {
+        let quick_keys = self.dump_quick_keys();
+        let roots = self.ids()
+            .filter(|id| self.nodes[id.0].parent.is_none())
+            .map(|id| self.dump_node(id, &quick_keys))
+            .collect();
+        Value::Array(roots)
+    }
+
+    fn dump_node(&self, id: NodeId, quick_keys: &[Option<String>]) -> Value {
+        let node = self.node(id);
+        let mut object = match node.to_json() {
+            Value::Object(fields) => fields,
+            _ => Map::new(),
+        };
+        let children: Vec<Value> = self.nodes[id.0].children.iter()
+            .map(|&child| self.dump_node(child, quick_keys))
+            .collect();
+        object.insert("name".to_string(), json!(node.name()));
+        object.insert("quick_key".to_string(), json!(quick_keys[id.0]));
+        object.insert("children".to_string(), json!(children));
+        Value::Object(object)
+    }
+
+    /// The whole tree as indented text, one node per line
+    pub fn dump_text(&self) -> String {
+        let quick_keys = self.dump_quick_keys();
+        self.ids()
+            .map(|id| {
+                let node = self.node(id);
+                let quick_key = match &quick_keys[id.0] {
+                    Some(keybind) => format!("({}) ", keybind),
+                    None => String::new(),
+                };
+                let focused = match node.is_focused() {
+                    true => " (focused)",
+                    false => "",
+                };
+                format!("{}{}{}{}", "  ".repeat(self.depth(id)), quick_key, node.name(), focused)
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    /// Move the viewport by a number of lines, until the cursor is moved with the keyboard
+    pub fn scroll(&mut self, lines: isize) {
+        let from = self.scroll.unwrap_or(self.rendered_from);
+        self.scroll = Some(from.saturating_add_signed(lines));
+    }
+
+    pub fn reset_scroll(&mut self) {
+        self.scroll = None;
+    }
+
+    /// The node drawn on a line of the plugin by the last render
+    pub fn node_at(&self, line: isize) -> Option<NodeId> {
+        let row = usize::try_from(line).ok()?.checked_sub(self.rendered_y)?;
+        self.rendered.get(row).copied().flatten()
+    }
+
+    /// Whether a click on a node's line hit its fold marker, which follows the indentation
+    pub fn is_fold_marker(&self, id: NodeId, column: usize) -> bool {
+        // the indentation and bullet of the list are at most 2 columns per level
+        self.fold(id).is_some() && column <= self.depth(id) * 2 + 3
+    }
+
+    pub fn select(&mut self, id: NodeId) {
+        self.cursor = id.0;
+    }
+
+    /// Expand or collapse the node itself, selecting it so the cursor is never hidden
+    pub fn toggle_fold(&mut self, id: NodeId) {
+        self.cursor = id.0;
+        match self.nodes[id.0].is_expanded {
+            true => self.collapse_node(id),
+            false => self.expand(id),
+        }
+    }
+
+    /// Panes of the selected tab, or of the tab of the selected pane
+    fn preview_panes(&self) -> Option<Vec<PreviewPane>> {
+        let id = self.current().ok()?;
+        let (tab, selected) = match self.nodes[id.0].kind {
+            NodeKind::Tab(_) => (id, None),
+            NodeKind::Pane(_) => (self.nodes[id.0].parent?, Some(id)),
+            _ => return None,
+        };
+        let panes = self.nodes[tab.0].children.iter()
+            .filter_map(|&pane| {
+                let node = self.node(pane);
+                node.geometry().map(|geometry| PreviewPane {
+                    title: node.name(),
+                    geometry,
+                    is_selected: Some(pane) == selected,
+                    is_focused: node.is_focused(),
+                })
+            })
+            .collect();
+        Some(panes)
+    }
+
+    /// The line of a node, labelled with its quick-select key
+    fn render_node(&self, id: NodeId, keybind: &str) -> NestedListItem {
+        let is_selected = id.0 == self.cursor;
+        let matches = self.matches.get(id.0).cloned().flatten().unwrap_or_default();
+        let node = self.node(id);
+        let item = match (is_selected, &self.editing) {
+            (true, Some(name)) => {
+                let prefix = render_prefix(keybind, self.fold(id), false);
+                let text = format!("{0}{1}_", prefix, name);
+                let text_len = text.chars().count();
+                NestedListItem::new(text).color_range(2, 0..text_len).selected()
+            }
+            (is_selected, _) => {
+                let is_marked = self.marked.contains(&node.key());
+                let prefix = render_prefix(keybind, self.fold(id), is_marked);
+                node.render(&prefix, is_selected, is_marked, &matches)
+            }
+        };
+        // the keys typed so far of a hint, which starts after the fold marker and the parenthesis
+        let item = match !self.hint.is_empty() && keybind.starts_with(&self.hint) {
+            true => item.color_range(1, 3..3 + self.hint.chars().count()),
+            false => item,
+        };
+        item.indent(self.depth(id))
+    }
+
+    /// The lines shown for a height, starting from the returned one: a line per visible node,
+    /// plus the prompt under the selected one
+    fn window(&self, rows: usize) -> (Vec<Option<NodeId>>, usize) {
+        let mut line_nodes = Vec::new();
+        let mut selected_line = 0;
+        for id in self.ids().filter(|&id| self.is_visible(id)) {
+            line_nodes.push(Some(id));
+            if id.0 == self.cursor {
+                selected_line = line_nodes.len() - 1;
+                if self.prompt.is_some() {
+                    line_nodes.push(None);
+                    selected_line += 1;
+                }
+            }
+        }
+        let last_from = line_nodes.len().saturating_sub(rows);
+        let from = match self.scroll {
+            Some(scroll) => scroll.min(last_from),
+            None => selected_line.saturating_sub(rows.saturating_sub(1) / 2).min(last_from),
+        };
+        (line_nodes.into_iter().skip(from).take(rows).collect(), from)
+    }
+
+    pub fn render(&mut self, host: &dyn Host, y: usize, rows: usize, cols: usize) {
+        let preview = match cols >= MIN_PREVIEW_COLS {
+            true => self.preview_panes().filter(|panes| !panes.is_empty()),
+            false => None,
+        };
+        let preview_cols = match preview {
+            Some(_) => cols / 2,
+            None => 0,
+        };
+        let (lines, from) = self.window(rows);
+        if self.scroll.is_some() {
+            self.scroll = Some(from);
+        }
+        self.rendered = lines;
+        self.rendered_y = y;
+        self.rendered_from = from;
+        self.rendered_rows = Some(rows);
+        // only the nodes on screen are labelled, top to bottom
+        self.quick_find = self.rendered.iter().flatten().map(|&id| Some(id)).collect();
+        let quick_keys = self.quick_keys(&self.quick_find);
+        let lines = self.rendered.iter()
+            .map(|line| match line {
+                Some(id) => self.render_node(*id, quick_keys[id.0].as_deref().unwrap_or_default()),
+                None => {
+                    let prompt = self.prompt.clone().unwrap_or_default();
+                    let prompt_len = prompt.chars().count();
+                    NestedListItem::new(prompt).indent(1).color_range(1, 0..prompt_len)
+                }
+            })
+            .collect();
+        host.print_nested_list(lines, 0, y, cols - preview_cols, rows);
+        if let Some(panes) = preview {
+            render_preview(&panes, cols - preview_cols + 1, y, preview_cols - 1, rows);
+        }
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use super::*;
+    use crate::config::GroupBy;
+    use crate::host::RecordingHost;
+    use std::collections::HashMap;
+
+    /// A session with a tab per entry, each having panes with the given titles
+    pub fn session(name: &str, is_current_session: bool, tabs: &[(&str, &[&str])]) -> SessionInfo {
+        let mut panes = HashMap::new();
+        let mut id = 0;
+        let tabs = tabs.iter().enumerate().map(|(position, (tab, titles))| {
+            let tab_panes = titles.iter().enumerate().map(|(i, title)| {
+                id += 1;
+                PaneInfo {
+                    id,
+                    title: title.to_string(),
+                    is_focused: i == 0,
+                    is_selectable: true,
+                    ..Default::default()
+                }
+            }).collect();
+            panes.insert(position, tab_panes);
+            TabInfo {
+                position,
+                name: tab.to_string(),
+                active: position == 0,
+                ..Default::default()
+            }
+        }).collect();
+        SessionInfo {
+            name: name.to_string(),
+            tabs,
+            panes: PaneManifest { panes },
+            is_current_session,
+            ..Default::default()
         }
-        let from = (self.cursor as usize).saturating_sub(rows.saturating_sub(1) / 2).min(lines.len().saturating_sub(rows));
-        print_nested_list(lines.into_iter().skip(from).take(rows).collect());
+    }
+
+    fn sessions() -> Vec<SessionInfo> {
+        vec![
+            session("alpha", true, &[("editor", &["vim", "shell"]), ("logs", &["tail"])]),
+            session("beta", false, &[("build", &["cargo"])]),
+            session("gamma", false, &[]),
+        ]
+    }
+
+    fn tree() -> SessionTree {
+        SessionTree::new(sessions(), Vec::new(), &Config::default())
+    }
+
+    fn visible(tree: &SessionTree) -> Vec<String> {
+        tree.ids()
+            .filter(|&id| tree.is_visible(id))
+            .map(|id| tree.node(id).name())
+            .collect()
+    }
+
+    fn selected(tree: &SessionTree) -> String {
+        tree.node(tree.current().unwrap()).name()
+    }
+
+    #[test]
+    fn moves_loop_around() {
+        let mut tree = tree();
+        assert_eq!(visible(&tree), ["alpha", "beta", "gamma"]);
+        tree.handle_up().unwrap();
+        assert_eq!(selected(&tree), "gamma");
+        tree.handle_down().unwrap();
+        assert_eq!(selected(&tree), "alpha");
+    }
+
+    #[test]
+    fn expand_then_collapse_back_to_the_parent() {
+        let mut tree = tree();
+        tree.handle_right().unwrap();
+        assert_eq!(visible(&tree), ["alpha", "editor", "logs", "beta", "gamma"]);
+        tree.handle_right().unwrap();
+        assert_eq!(selected(&tree), "editor");
+        tree.handle_left().unwrap();
+        assert_eq!(selected(&tree), "alpha");
+        assert_eq!(visible(&tree), ["alpha", "beta", "gamma"]);
+    }
+
+    #[test]
+    fn collapsing_a_session_collapses_its_tabs() {
+        let mut tree = tree();
+        tree.expand_all();
+        assert_eq!(visible(&tree).len(), 10);
+        let alpha = tree.find(&NodeKey::Session("alpha".to_string())).unwrap();
+        tree.toggle_fold(alpha);
+        tree.toggle_fold(alpha);
+        assert_eq!(visible(&tree), ["alpha", "editor", "logs", "beta", "build", "cargo", "gamma"]);
+    }
+
+    #[test]
+    fn update_keeps_the_selection_and_folds() {
+        let mut tree = tree();
+        tree.handle_down().unwrap();
+        tree.handle_right().unwrap();
+        tree.handle_down().unwrap();
+        assert_eq!(selected(&tree), "build");
+        let sessions = vec![
+            session("beta", false, &[("build", &["cargo"])]),
+            session("alpha", true, &[("editor", &["vim", "shell"]), ("logs", &["tail"])]),
+        ];
+        tree.update(sessions, Vec::new(), &Config::default());
+        assert_eq!(visible(&tree), ["beta", "build", "alpha"]);
+        assert_eq!(selected(&tree), "build");
+    }
+
+    #[test]
+    fn filtering_keeps_the_ancestors_of_matches() {
+        let mut tree = tree();
+        tree.set_query("carg".to_string());
+        assert_eq!(visible(&tree), ["beta", "build", "cargo"]);
+        assert_eq!(selected(&tree), "cargo");
+    }
+
+    #[test]
+    fn nothing_is_selected_when_the_filter_matches_nothing() {
+        let mut tree = tree();
+        let host = RecordingHost::default();
+        tree.set_query("zzz".to_string());
+        assert_eq!(visible(&tree), Vec::<String>::new());
+        assert_eq!(tree.switch_to_selected(&host), Err("no node selected".to_string()));
+        assert!(tree.targets().is_err());
+        assert!(tree.describe_kill().is_err());
+        assert_eq!(host.take(), []);
+        tree.set_query(String::new());
+        assert_eq!(selected(&tree), "alpha");
+    }
+
+    #[test]
+    fn quick_select_focuses_the_nth_visible_node() {
+        let mut tree = tree();
+        let host = RecordingHost::default();
+        tree.render(&host, 0, 20, 40);
+        assert_eq!(tree.switch_by_index(&host, 1), Ok("beta".to_string()));
+        assert_eq!(host.take(), [HostCommand::SwitchSession("beta".to_string()), HostCommand::HideSelf]);
+        assert!(tree.switch_by_index(&host, 3).is_err());
+    }
+
+    #[test]
+    fn quick_select_keys_follow_the_rows_on_screen() {
+        let mut tree = tree();
+        let host = RecordingHost::default();
+        tree.render(&host, 0, 20, 40);
+        tree.handle_right().unwrap();
+        tree.render(&host, 0, 20, 40);
+        assert_eq!(tree.switch_by_index(&host, 1), Ok("alpha".to_string()));
+        assert_eq!(host.take(), [HostCommand::FocusOrCreateTab("editor".to_string()), HostCommand::HideSelf]);
+        // scrolled down to gamma, the first row on screen is labelled 0
+        tree.handle_up().unwrap();
+        tree.render(&host, 0, 2, 40);
+        assert_eq!(tree.switch_by_index(&host, 0), Ok("beta".to_string()));
+        assert!(tree.switch_by_index(&host, 2).is_err());
+    }
+
+    #[test]
+    fn hints_span_several_keys_once_the_alphabet_runs_out() {
+        let alphabet = HintAlphabet::parse("asdf").unwrap();
+        assert_eq!(alphabet.hints(3), ["a", "s", "d"]);
+        assert_eq!(alphabet.hints(6), ["s", "d", "f", "aa", "as", "ad"]);
+        assert_eq!(HintAlphabet::default().hints(37)[34..], ["Z", "00", "01"]);
+        assert!(HintAlphabet::parse("a").is_err());
+        assert!(HintAlphabet::parse("asa").is_err());
+    }
+
+    #[test]
+    fn hints_are_typed_one_key_at_a_time() {
+        let config = Config {
+            hint_alphabet: HintAlphabet::parse("asdf").unwrap(),
+            ..Default::default()
+        };
+        let mut tree = SessionTree::new(sessions(), Vec::new(), &config);
+        let host = RecordingHost::default();
+        tree.expand_all();
+        tree.render(&host, 0, 20, 40);
+        // 10 rows: d, f, then aa to sf
+        assert_eq!(tree.type_hint('s'), Ok(None));
+        assert_eq!(tree.type_hint('a'), Ok(Some(6)));
+        assert_eq!(tree.switch_by_index(&host, 6), Ok("beta".to_string()));
+        assert!(tree.type_hint('q').is_err());
+        assert_eq!(tree.type_hint('f'), Ok(Some(1)));
+        assert_eq!(tree.switch_by_index(&host, 1), Ok("alpha".to_string()));
+        assert_eq!(host.take(), [
+            HostCommand::SwitchSession("beta".to_string()),
+            HostCommand::HideSelf,
+            HostCommand::FocusOrCreateTab("editor".to_string()),
+            HostCommand::HideSelf,
+        ]);
+    }
+
+    #[test]
+    fn dump_labels_the_rows_the_last_render_showed() {
+        let mut tree = tree();
+        assert_eq!(tree.dump_text().lines().filter(|line| line.starts_with('(')).count(), 3);
+        tree.handle_down().unwrap();
+        tree.handle_down().unwrap();
+        tree.render(&RecordingHost::default(), 0, 2, 40);
+        let dump = tree.dump_text();
+        let labelled: Vec<&str> = dump.lines().filter(|line| line.starts_with('(')).collect();
+        assert_eq!(labelled, ["(0) beta", "(1) gamma"]);
+    }
+
+    #[test]
+    fn a_session_can_share_the_name_of_a_group() {
+        let config = Config {
+            group_by: Some(GroupBy::parse("separator:/").unwrap()),
+            ..Default::default()
+        };
+        let sessions = vec![session("acme", true, &[]), session("acme/api", false, &[]), session("acme/web", false, &[])];
+        let mut tree = SessionTree::new(sessions, Vec::new(), &config);
+        tree.expand_all();
+        assert_eq!(visible(&tree), ["acme", "acme", "acme/api", "acme/web"]);
+        let group = tree.find(&NodeKey::Group("acme".to_string())).unwrap();
+        let session = tree.find(&NodeKey::Session("acme".to_string())).unwrap();
+        assert_ne!(group, session);
+        assert_eq!(tree.nodes[group.0].children.len(), 2);
+        assert!(tree.nodes[session.0].parent.is_none());
+    }
+
+    #[test]
+    fn only_nodes_of_the_current_session_can_be_renamed() {
+        let mut tree = tree();
+        tree.handle_right().unwrap();
+        tree.handle_down().unwrap();
+        assert_eq!(tree.start_rename(), Ok(()));
+        assert_eq!(tree.editing_mut().cloned(), Some("editor".to_string()));
+        tree.cancel_rename();
+        tree.select(tree.find(&NodeKey::Session("beta".to_string())).unwrap());
+        assert_eq!(tree.start_rename(), Err("can only rename the current session".to_string()));
+        assert_eq!(tree.editing_mut(), None);
+    }
+
+    #[test]
+    fn tabs_are_focused_in_place_or_through_their_session() {
+        let mut tree = tree();
+        let host = RecordingHost::default();
+        tree.expand_all();
+        tree.focus_node(&host, &NodeKey::Tab("alpha".to_string(), 1)).unwrap();
+        tree.focus_node(&host, &NodeKey::Tab("beta".to_string(), 0)).unwrap();
+        assert_eq!(host.take(), [
+            HostCommand::FocusOrCreateTab("logs".to_string()),
+            HostCommand::HideSelf,
+            HostCommand::SwitchSessionWithFocus("beta".to_string(), Some(0), None),
+            HostCommand::HideSelf,
+        ]);
+    }
+
+    #[test]
+    fn marked_sessions_are_killed_at_once() {
+        let mut tree = tree();
+        let host = RecordingHost::default();
+        tree.toggle_mark().unwrap();
+        tree.toggle_mark().unwrap();
+        let keys = tree.targets().unwrap();
+        tree.kill_nodes(&host, &keys).unwrap();
+        assert_eq!(host.take(), [HostCommand::KillSessions(vec!["alpha".to_string(), "beta".to_string()])]);
+    }
+
+    #[test]
+    fn marked_tabs_are_closed_from_the_last_one() {
+        let sessions = vec![session("alpha", true, &[("a", &["x"]), ("b", &["y"]), ("c", &["z"])])];
+        let tree = SessionTree::new(sessions, Vec::new(), &Config::default());
+        let host = RecordingHost::default();
+        let alpha = || "alpha".to_string();
+        let keys = [NodeKey::Tab(alpha(), 0), NodeKey::Pane(alpha(), (3, false)), NodeKey::Tab(alpha(), 2)];
+        tree.kill_nodes(&host, &keys).unwrap();
+        assert_eq!(host.take(), [HostCommand::CloseTab(2), HostCommand::CloseTab(0)]);
     }
 }
@@ -0,0 +1,94 @@
+use zellij_tile::prelude::*;
+use std::str::FromStr;
+
+use crate::fixture::{assert_snapshot, describe_item, Fixture};
+use crate::host::RecordingHost;
+use crate::State;
+
+/// Load a fixture and press the keys, rendering whenever zellij would, then describe the last
+/// rendered tree followed by the commands issued along the way
+fn run(fixture: &str, keys: &[&str], rows: usize, cols: usize) -> String {
+    let fixture = Fixture::load(fixture);
+    let host = RecordingHost::default();
+    let mut state = State {
+        host: Box::new(host.clone()),
+        ..Default::default()
+    };
+    state.load(fixture.config.clone());
+    host.take();
+    let keys = keys.iter().map(|key| {
+        let key = KeyWithModifier::from_str(key).unwrap_or_else(|_| panic!("invalid key '{}'", key));
+        Event::Key(key)
+    });
+    for event in std::iter::once(fixture.session_update()).chain(keys) {
+        if state.update(event) {
+            state.render(rows, cols);
+        }
+    }
+    let mut lines: Vec<String> = host.frame().iter().map(describe_item).collect();
+    lines.push("--".to_string());
+    lines.extend(host.take().iter().map(|command| format!("{:?}", command)));
+    lines.join("\n") + "\n"
+}
+
+#[test]
+fn sample_collapsed() {
+    assert_snapshot("sample_collapsed", &run("sample", &[], 20, 50));
+}
+
+#[test]
+fn sample_expanded() {
+    let keys = ["Right", "Down", "Right", "Down", "Down", "Down", "Down", "Right"];
+    assert_snapshot("sample_expanded", &run("sample", &keys, 30, 50));
+}
+
+#[test]
+fn sample_scrolled_to_the_selection() {
+    let keys = ["Right", "Down", "Right", "Down", "Down", "Down", "Down", "Right", "Down", "Down", "Down"];
+    assert_snapshot("sample_scrolled_to_the_selection", &run("sample", &keys, 8, 50));
+}
+
+#[test]
+fn sample_filtered() {
+    let keys = ["/", "t", "a", "b", "3", "Enter"];
+    assert_snapshot("sample_filtered", &run("sample", &keys, 20, 50));
+}
+
+#[test]
+fn sample_quick_select() {
+    assert_snapshot("sample_quick_select", &run("sample", &["Right", "2"], 20, 50));
+}
+
+#[test]
+fn groups_collapsed() {
+    assert_snapshot("groups_collapsed", &run("groups", &[], 20, 50));
+}
+
+#[test]
+fn groups_expanded_with_exited_sessions() {
+    let keys = ["Right", "Up", "Right"];
+    assert_snapshot("groups_expanded_with_exited_sessions", &run("groups", &keys, 20, 50));
+}
+
+#[test]
+fn groups_kill_after_confirmation() {
+    assert_snapshot("groups_kill_prompt", &run("groups", &["x"], 20, 50));
+    assert_snapshot("groups_kill_confirmed", &run("groups", &["x", "y"], 20, 50));
+}
+
+#[test]
+fn hints_partially_typed() {
+    assert_snapshot("hints_partially_typed", &run("hints", &["a"], 20, 50));
+}
+
+#[test]
+fn hints_typed() {
+    assert_snapshot("hints_typed", &run("hints", &["a", "d"], 20, 50));
+}
+
+#[test]
+fn groups_kill_a_tab_by_its_position() {
+    // db is at position 2, the tab before it having been closed
+    let keys = ["Right", "Down", "Right", "Down", "Down", "x", "y"];
+    assert_snapshot("groups_kill_a_tab_by_its_position", &run("groups", &keys, 20, 50));
+}
@@ -0,0 +1,226 @@
+use zellij_tile::prelude::*;
+use std::cmp::Ordering;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::history::History;
+
+/// Order of the tree, from the `sort` option and cycled with the sort key
+#[derive(Clone, Copy, Default, PartialEq)]
+pub enum SortOrder {
+    // as reported by zellij
+    #[default]
+    Original,
+    // most recently switched to first
+    Mru,
+    Alphabetical,
+    // digits compared as numbers, `proj-2` before `proj-10`
+    Natural,
+    // most tabs first, tabs with the most panes first
+    Tabs,
+    // most panes first
+    Panes,
+    // most connected clients first
+    Clients,
+    CurrentFirst,
+}
+
+const SORT_ORDERS: [SortOrder; 8] = [
+    SortOrder::Original,
+    SortOrder::Mru,
+    SortOrder::Alphabetical,
+    SortOrder::Natural,
+    SortOrder::Tabs,
+    SortOrder::Panes,
+    SortOrder::Clients,
+    SortOrder::CurrentFirst,
+];
+
+impl SortOrder {
+    pub fn next(&self) -> Self {
+        let position = SORT_ORDERS.iter().position(|order| order == self).unwrap_or_default();
+        SORT_ORDERS[(position + 1) % SORT_ORDERS.len()]
+    }
+}
+
+impl FromStr for SortOrder {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        SORT_ORDERS.iter()
+            .find(|order| order.to_string() == s)
+            .copied()
+            .ok_or(format!("unknown sort order '{}'", s))
+    }
+}
+
+impl fmt::Display for SortOrder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SortOrder::Original => write!(f, "original"),
+            SortOrder::Mru => write!(f, "mru"),
+            SortOrder::Alphabetical => write!(f, "alphabetical"),
+            SortOrder::Natural => write!(f, "natural"),
+            SortOrder::Tabs => write!(f, "tabs"),
+            SortOrder::Panes => write!(f, "panes"),
+            SortOrder::Clients => write!(f, "clients"),
+            SortOrder::CurrentFirst => write!(f, "current_first"),
+        }
+    }
+}
+
+/// Sort the sessions, their tabs and the panes of each tab, ties keeping their original order
+pub fn sort_sessions(sessions: &mut [SessionInfo], order: SortOrder, history: &History) {
+    match order {
+        SortOrder::Original => (),
+        // sessions never switched to keep their original order, after the others
+        SortOrder::Mru => sessions.sort_by_key(|session| history.rank(&session.name).unwrap_or(usize::MAX)),
+        SortOrder::Alphabetical => sessions.sort_by(|a, b| a.name.cmp(&b.name)),
+        SortOrder::Natural => sessions.sort_by(|a, b| natural_cmp(&a.name, &b.name)),
+        SortOrder::Tabs => sessions.sort_by_key(|session| std::cmp::Reverse(session.tabs.len())),
+        SortOrder::Panes => sessions.sort_by_key(|session| std::cmp::Reverse(count_panes(session.panes.panes.values().flatten()))),
+        SortOrder::Clients => sessions.sort_by_key(|session| std::cmp::Reverse(session.connected_clients)),
+        SortOrder::CurrentFirst => sessions.sort_by_key(|session| !session.is_current_session),
+    }
+    for session in sessions.iter_mut() {
+        match order {
+            SortOrder::Alphabetical => session.tabs.sort_by(|a, b| a.name.cmp(&b.name)),
+            SortOrder::Natural => session.tabs.sort_by(|a, b| natural_cmp(&a.name, &b.name)),
+            SortOrder::Tabs | SortOrder::Panes => {
+                let panes = &session.panes.panes;
+                session.tabs.sort_by_key(|tab| std::cmp::Reverse(count_panes(panes.get(&tab.position).into_iter().flatten())));
+            }
+            _ => (),
+        }
+        for panes in session.panes.panes.values_mut() {
+            match order {
+                SortOrder::Alphabetical => panes.sort_by(|a, b| a.title.cmp(&b.title)),
+                SortOrder::Natural => panes.sort_by(|a, b| natural_cmp(&a.title, &b.title)),
+                _ => (),
+            }
+        }
+    }
+}
+
+/// Terminal panes, plugin panes being mostly the ui of zellij
+fn count_panes<'a>(panes: impl Iterator<Item = &'a PaneInfo>) -> usize {
+    panes.filter(|pane| !pane.is_plugin).count()
+}
+
+/// Compare strings with their runs of digits compared as numbers, the same number with fewer
+/// leading zeros coming first, `a1` before `a01`
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a = a.chars().peekable();
+    let mut b = b.chars().peekable();
+    // the first difference in leading zeros, only used when the strings are otherwise equal
+    let mut zeros = Ordering::Equal;
+    loop {
+        match (a.peek().copied(), b.peek().copied()) {
+            (None, None) => return zeros,
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let x = take_number(&mut a);
+                let y = take_number(&mut b);
+                zeros = zeros.then(x.len().cmp(&y.len()));
+                // compare by length first so that numbers of any size are handled
+                let x = x.trim_start_matches('0');
+                let y = y.trim_start_matches('0');
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(y));
+                if ordering != Ordering::Equal {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                if x != y {
+                    return x.cmp(&y);
+                }
+                a.next();
+                b.next();
+            }
+        }
+    }
+}
+
+fn take_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> String {
+    let mut number = String::new();
+    while let Some(c) = chars.next_if(|c| c.is_ascii_digit()) {
+        number.push(c);
+    }
+    number
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::host::RecordingHost;
+    use crate::sessiontree::tests::session;
+
+    fn names(sessions: &[SessionInfo]) -> Vec<&str> {
+        sessions.iter().map(|session| session.name.as_str()).collect()
+    }
+
+    #[test]
+    fn mru_puts_sessions_never_switched_to_last() {
+        let host = RecordingHost::default();
+        let mut history = History::default();
+        history.record(&host, "beta", "delta").unwrap();
+        let mut sessions = ["alpha", "beta", "gamma", "delta", "epsilon"].map(|name| session(name, false, &[]));
+        sort_sessions(&mut sessions, SortOrder::Mru, &history);
+        assert_eq!(names(&sessions), ["delta", "beta", "alpha", "gamma", "epsilon"]);
+    }
+
+    #[test]
+    fn natural_order_compares_numbers_by_value() {
+        assert_eq!(natural_cmp("proj-2", "proj-10"), Ordering::Less);
+        assert_eq!(natural_cmp("proj-10", "proj-10"), Ordering::Equal);
+        // leading zeros only break ties
+        assert_eq!(natural_cmp("a1", "a01"), Ordering::Less);
+        assert_eq!(natural_cmp("a01", "a2"), Ordering::Less);
+        assert_eq!(natural_cmp("a01b", "a1c"), Ordering::Less);
+        assert_eq!(natural_cmp("a00", "a0"), Ordering::Greater);
+    }
+
+    #[test]
+    fn natural_order_compares_digits_and_other_characters_as_characters() {
+        assert_eq!(natural_cmp("proj", "proj1"), Ordering::Less);
+        assert_eq!(natural_cmp("proj-", "proj1"), Ordering::Less);
+        assert_eq!(natural_cmp("1proj", "proj"), Ordering::Less);
+        assert_eq!(natural_cmp("a1b", "a1"), Ordering::Greater);
+    }
+
+    #[test]
+    fn natural_order_handles_numbers_of_any_size() {
+        let huge = format!("v{}", "9".repeat(40));
+        assert_eq!(natural_cmp(&huge, "v10"), Ordering::Greater);
+        assert_eq!(natural_cmp(&huge, &format!("v1{}", "0".repeat(40))), Ordering::Less);
+        assert_eq!(natural_cmp("v18446744073709551616", "v18446744073709551615"), Ordering::Greater);
+    }
+
+    fn tab_names(session: &SessionInfo) -> Vec<&str> {
+        session.tabs.iter().map(|tab| tab.name.as_str()).collect()
+    }
+
+    fn pane_titles(session: &SessionInfo, position: usize) -> Vec<&str> {
+        session.panes.panes[&position].iter().map(|pane| pane.title.as_str()).collect()
+    }
+
+    #[test]
+    fn alphabetical_order_sorts_tabs_and_panes() {
+        let mut sessions = [session("work", true, &[("zeta", &["vim", "cargo"]), ("alpha", &["logs"])])];
+        sort_sessions(&mut sessions, SortOrder::Alphabetical, &History::default());
+        assert_eq!(tab_names(&sessions[0]), ["alpha", "zeta"]);
+        assert_eq!(pane_titles(&sessions[0], 0), ["cargo", "vim"]);
+    }
+
+    #[test]
+    fn tabs_order_puts_the_busiest_first() {
+        let mut sessions = [
+            session("small", false, &[("one", &["a"])]),
+            session("big", true, &[("few", &["a"]), ("many", &["a", "b", "c"]), ("some", &["a", "b"])]),
+        ];
+        sort_sessions(&mut sessions, SortOrder::Tabs, &History::default());
+        assert_eq!(names(&sessions), ["big", "small"]);
+        assert_eq!(tab_names(&sessions[0]), ["many", "some", "few"]);
+    }
+}
@@ -0,0 +1,88 @@
+use zellij_tile::prelude::*;
+use std::collections::VecDeque;
+
+const LOG_SIZE: usize = 20;
+const LOG_ROWS: usize = 5;
+
+#[derive(Clone, Copy)]
+pub enum Severity {
+    Info,
+    Error,
+}
+
+#[derive(Clone)]
+pub struct Message {
+    severity: Severity,
+    text: String,
+}
+
+impl Message {
+    fn render(&self) {
+        let text_len = self.text.chars().count();
+        let text = match self.severity {
+            Severity::Info => Text::new(&self.text).color_range(2, 0..text_len),
+            Severity::Error => Text::new(&self.text).color_range(0, 0..text_len),
+        };
+        print_text(text);
+        println!();
+    }
+}
+
+/// Result of the last action, along with a log of the recent ones
+#[derive(Default)]
+pub struct Status {
+    current: Option<Message>,
+    log: VecDeque<Message>,
+    show_log: bool,
+}
+
+impl Status {
+    pub fn info(&mut self, text: String) {
+        self.push(Severity::Info, text);
+    }
+
+    pub fn error(&mut self, text: String) {
+        self.push(Severity::Error, text);
+    }
+
+    /// Show the error of a failed action, successful ones stay silent
+    pub fn report(&mut self, result: Result<(), String>) {
+        if let Err(error) = result {
+            self.error(error);
+        }
+    }
+
+    fn push(&mut self, severity: Severity, text: String) {
+        let message = Message { severity, text };
+        self.log.push_back(message.clone());
+        if self.log.len() > LOG_SIZE {
+            self.log.pop_front();
+        }
+        self.current = Some(message);
+    }
+
+    pub fn clear(&mut self) {
+        self.current = None;
+    }
+
+    pub fn toggle_log(&mut self) {
+        self.show_log = !self.show_log;
+    }
+
+    /// Render the status line, followed by the log when toggled, returning the number of rows used
+    pub fn render(&self) -> usize {
+        match &self.current {
+            Some(message) => message.render(),
+            None => println!(),
+        }
+        if !self.show_log {
+            return 1;
+        }
+        println!("Recent messages:");
+        let skip = self.log.len().saturating_sub(LOG_ROWS);
+        for message in self.log.iter().skip(skip) {
+            message.render();
+        }
+        2 + self.log.len() - skip
+    }
+}
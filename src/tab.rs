@@ -1,100 +1,103 @@
 use zellij_tile::prelude::*;
-use std::rc::Rc;
-use std::cell::RefCell;
+use serde_json::{json, Value};
 
-use crate::sessiontree::Node;
+use crate::host::{Host, HostCommand};
+use crate::preview::PaneGeometry;
+use crate::sessiontree::{Node, NodeKey};
+use crate::utils::{highlight_mark, highlight_matches};
 
 pub struct Tab {
-    index: usize,
     name: String,
     position: usize,
     active: bool,
-    session: Rc<RefCell<dyn Node>>,
-    panes: Vec<Rc<RefCell<dyn Node>>>,
-    shown: bool,
-    is_expanded: bool,
+    session: String,
+    is_current_session: bool,
 }
 
 impl Tab {
-    pub fn new(index: usize, name: String, position: usize, session: Rc<RefCell<dyn Node>>, active: bool) -> Self {
+    pub fn new(name: String, position: usize, active: bool, session: &dyn Node) -> Self {
         Self {
-            index,
             name,
             position,
             active,
-            session: session.clone(),
-            panes: Vec::new(),
-            shown: false,
-            is_expanded: false,
+            session: session.identifier(),
+            is_current_session: session.is_focused(),
         }
     }
+
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    pub fn session(&self) -> &str {
+        &self.session
+    }
+
+    pub fn is_current_session(&self) -> bool {
+        self.is_current_session
+    }
 }
 
 impl Node for Tab {
-    fn index(&self) -> usize {
-        self.index
-    }
     fn identifier(&self) -> String {
         self.position.to_string()
     }
+    fn key(&self) -> NodeKey {
+        NodeKey::Tab(self.session.clone(), self.position)
+    }
+    fn name(&self) -> String {
+        self.name.clone()
+    }
     fn is_focused(&self) -> bool {
         self.active
     }
-    fn focus(&self) -> Result<(), String> {
-        let session = self.session.borrow();
-        if session.is_focused() {
-            focus_or_create_tab(self.name.as_str());
+    fn focus(&self, host: &dyn Host) -> Result<(), String> {
+        if self.is_current_session {
+            host.run(HostCommand::FocusOrCreateTab(self.name.clone()));
         } else {
-            switch_session_with_focus(&session.identifier(), Some(self.position), None);
+            host.run(HostCommand::SwitchSessionWithFocus(self.session.clone(), Some(self.position), None));
         }
-        hide_self();
+        host.run(HostCommand::HideSelf);
         Ok(())
     }
-    fn kill(&self) -> Result<(), String> {
-        Err("cannot kill tab".to_string())
-    }
-    fn parent(&self) -> Option<Rc<RefCell<dyn Node>>> {
-        Some(self.session.clone())
-    }
-    fn add_child(&mut self, child: Rc<RefCell<dyn Node>>) {
-        self.panes.push(child);
+    fn kill(&self, host: &dyn Host) -> Result<(), String> {
+        if !self.is_current_session {
+            return Err("cannot kill a tab in another session".to_string());
+        }
+        host.run(HostCommand::CloseTab(self.position));
+        Ok(())
     }
-    fn is_shown(&self) -> bool {
-        self.shown
+    fn can_rename(&self) -> Result<(), String> {
+        match self.is_current_session {
+            true => Ok(()),
+            false => Err("can only rename tabs in the current session".to_string()),
+        }
     }
-    fn show(&mut self) {
-        self.shown = true;
+    fn rename(&self, host: &dyn Host, name: &str) -> Result<(), String> {
+        self.can_rename()?;
+        host.run(HostCommand::RenameTab(self.position as u32, name.to_string()));
+        Ok(())
     }
-    fn hide(&mut self) {
-        self.shown = false
+    fn geometry(&self) -> Option<PaneGeometry> {
+        None
     }
-    fn is_expanded(&self) -> bool {
-         self.is_expanded
+    fn to_json(&self) -> Value {
+        json!({
+            "tab": {
+                "name": self.name,
+                "position": self.position,
+                "is_active": self.active,
+            },
+        })
     }
-    fn expand(&mut self) {
-        if self.is_expanded {
-            return;
-        }
-        self.is_expanded = true;
-        for pane in self.panes.iter() {
-            pane.borrow_mut().show();
-        }
-    }
-    fn collapse(&mut self) {
-        if !self.is_expanded {
-            return;
-        }
-        self.is_expanded = false;
-        for pane in self.panes.iter() {
-            pane.borrow_mut().hide();
-        }
-    }
-    fn render(&self, keybind: String, is_selected: bool) -> NestedListItem {
-        let text = format!("({0}) {1}", keybind, self.name);
-        let text_len = text.len();
-        match is_selected {
-            true => NestedListItem::new(text).indent(1).color_range(0, 0..text_len).selected(),
-            false => NestedListItem::new(text).indent(1),
-        }
+    fn render(&self, prefix: &str, is_selected: bool, is_marked: bool, matches: &[usize]) -> NestedListItem {
+        let text = format!("{0}{1}", prefix, self.name);
+        let text_len = text.chars().count();
+        let item = match is_selected {
+            true => NestedListItem::new(text).color_range(0, 0..text_len).selected(),
+            false => NestedListItem::new(text),
+        };
+        let item = highlight_mark(item, prefix, is_marked);
+        highlight_matches(item, prefix.chars().count(), matches)
     }
 }
@@ -1,45 +1,70 @@
-pub struct IdGenerator {
-    id: usize,
-}
+use std::time::Duration;
+use zellij_tile::prelude::NestedListItem;
 
-impl IdGenerator {
-    pub fn new() -> Self {
-        Self {
-            id: 0,
+/// Case insensitive subsequence match, returning the indices of the matched characters
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<Vec<usize>> {
+    let mut pattern = pattern.chars().peekable();
+    let mut indices = Vec::new();
+    for (i, c) in text.chars().enumerate() {
+        match pattern.peek() {
+            Some(p) if p.to_lowercase().eq(c.to_lowercase()) => {
+                indices.push(i);
+                pattern.next();
+            }
+            Some(_) => (),
+            None => break,
         }
     }
-    pub fn next(&mut self) -> usize {
-        let id = self.id;
-        self.id += 1;
-        id
+    match pattern.peek() {
+        Some(_) => None,
+        None => Some(indices),
     }
 }
 
-pub struct KeybindGenerator {
-    id: usize,
+/// "▸ (3) " in front of a node's name, the fold marker being blank for nodes without children,
+/// followed by "* " when it is marked
+pub fn render_prefix(keybind: &str, fold: Option<bool>, is_marked: bool) -> String {
+    let fold = match fold {
+        Some(true) => "▾",
+        Some(false) => "▸",
+        None => " ",
+    };
+    match is_marked {
+        true => format!("{0} ({1}) * ", fold, keybind),
+        false => format!("{0} ({1}) ", fold, keybind),
+    }
 }
 
-impl KeybindGenerator {
-    pub fn new() -> Self {
-        Self {
-            id: 0,
-        }
+/// Highlight the mark rendered at the end of the prefix by `render_prefix`
+pub fn highlight_mark(item: NestedListItem, prefix: &str, is_marked: bool) -> NestedListItem {
+    let position = prefix.chars().count().saturating_sub(2);
+    match is_marked {
+        true => item.color_range(1, position..=position),
+        false => item,
     }
+}
 
-    pub fn next(&mut self) -> String {
-        let id = self.id;
-        self.id += 1;
-        to_keybind(id)
-    }
+/// Highlight the matched characters of a name rendered at the given offset
+pub fn highlight_matches(item: NestedListItem, offset: usize, matches: &[usize]) -> NestedListItem {
+    matches.iter().fold(item, |item, i| item.color_range(3, offset + i..=offset + i))
 }
 
-pub fn to_keybind(index: usize) -> String {
-    if index >= 36 {
-        return ' '.to_string();
-    }
-    if index < 10 {
-        return format!("{}", index);
+/// Human readable age of an exited session, eg. "3 hours ago"
+pub fn format_age(age: Duration) -> String {
+    let seconds = age.as_secs();
+    let (value, unit) = match seconds {
+        0..=59 => (seconds, "second"),
+        60..=3599 => (seconds / 60, "minute"),
+        3600..=86399 => (seconds / 3600, "hour"),
+        _ => (seconds / 86400, "day"),
+    };
+    format!("{} ago", pluralize(value as usize, unit))
+}
+
+/// "1 tab", "3 tabs"
+pub fn pluralize(count: usize, unit: &str) -> String {
+    match count {
+        1 => format!("{} {}", count, unit),
+        _ => format!("{} {}s", count, unit),
     }
-    let ascii_value = (b'A' as usize + index - 10) as u32;
-    char::from_u32(ascii_value).expect("ascii value out of range").to_string()
 }